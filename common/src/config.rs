@@ -1,6 +1,22 @@
-use crate::Result;
-use serde::Deserialize;
-use std::path::Path;
+use crate::{Result, error::ErrorClass, state::Dex};
+use alloy::{consensus::TxType, primitives::U256};
+use serde::{Deserialize, Deserializer};
+use std::{path::Path, time::Duration};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("`{field}` of `{setting}` is {value} bps, it can be at most 10000")]
+    BpsOutOfRange { setting: &'static str, field: &'static str, value: u32 },
+
+    #[error(
+        "`slippage_bps` of `hashflow_slippage` is {0} bps, it must be 0 since Hashflow quotes are firm"
+    )]
+    FirmQuoteSlippage(u32),
+
+    #[error("`{setting}` is {value}, it must be a positive number of transactions per second")]
+    InvalidMaxTps { setting: &'static str, value: f64 },
+}
 
 #[derive(Deserialize, Debug)]
 pub struct Config {
@@ -14,9 +30,31 @@ pub struct Config {
     pub kinza_deposit_count: [u32; 2],
     pub shmonad_deposit_count: [u32; 2],
 
+    // withdraw, none if not set
+    #[serde(default)]
+    pub apriori_withdraw_count: [u32; 2],
+    #[serde(default)]
+    pub kinza_withdraw_count: [u32; 2],
+    #[serde(default)]
+    pub shmonad_withdraw_count: [u32; 2],
+
+    // wmon, none if not set
+    #[serde(default)]
+    pub wmon_wrap_count: [u32; 2],
+    #[serde(default)]
+    pub wmon_unwrap_count: [u32; 2],
+
     // nft
     pub nad_domains_count: [u32; 2],
 
+    // slippage
+    #[serde(default = "Slippage::pool_default")]
+    pub ambient_slippage: Slippage,
+    #[serde(default = "Slippage::pool_default")]
+    pub bean_slippage: Slippage,
+    #[serde(default = "Slippage::firm_quote_default")]
+    pub hashflow_slippage: Slippage,
+
     // bridge
     pub need_bridge: bool,
     pub bridge_amount_range: [f64; 2],
@@ -27,18 +65,342 @@ pub struct Config {
     pub deposit_ratio: [u32; 2],
     pub swap_ratio: [u32; 2],
     pub restart_thread_delay: u64,
+    /// Strict if not set, as before the nonce managers could be picked.
+    #[serde(default)]
+    pub nonce_manager: NonceManagerKind,
+    /// How many accounts are warmed up at the same time. Zero, or not set, for no limit.
+    #[serde(default)]
+    pub max_concurrent_accounts: usize,
 
     // rpc
-    pub monad_rpc_url: String,
-    pub base_rpc_url: String,
+    // `monad_rpc_url` and `base_rpc_url` with a single url are what older configs have
+    #[serde(alias = "monad_rpc_url", deserialize_with = "one_or_many")]
+    pub monad_rpc_urls: Vec<RpcEndpoint>,
+    #[serde(alias = "base_rpc_url", deserialize_with = "one_or_many")]
+    pub base_rpc_urls: Vec<RpcEndpoint>,
+    #[serde(default = "default_rpc_cooldown")]
+    pub rpc_cooldown: u64,
+    #[serde(default = "default_rpc_health_check_interval")]
+    pub rpc_health_check_interval: u64,
+    /// The most transactions broadcast per second on a chain across every account. Unlimited
+    /// if not set.
+    pub monad_max_tps: Option<f64>,
+    pub base_max_tps: Option<f64>,
+
+    // fees, the estimated ones without a cap if not set
+    #[serde(default)]
+    pub monad_fee_policy: FeePolicy,
+    #[serde(default)]
+    pub base_fee_policy: FeePolicy,
+
+    // retries
+    #[serde(default)]
+    pub retry_policy: RetryPolicy,
+}
+
+/// Slippage settings of a DEX.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct Slippage {
+    /// How far below the quote the amount out may land, in basis points. Zero for Hashflow,
+    /// whose quotes are firm.
+    pub slippage_bps: u32,
+    /// The highest price impact of a quote that is still traded, in basis points.
+    pub max_price_impact_bps: u32,
+}
+
+impl Slippage {
+    /// The 1% the pool swaps had before slippage could be set, without a price impact limit.
+    fn pool_default() -> Self {
+        Self { slippage_bps: 100, max_price_impact_bps: 10_000 }
+    }
+
+    /// No slippage, since the quote is firm, and no price impact limit.
+    fn firm_quote_default() -> Self {
+        Self { slippage_bps: 0, max_price_impact_bps: 10_000 }
+    }
+}
+
+/// An RPC url, either on its own or with a weight. A url on its own has a weight of 1.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum RpcEndpoint {
+    Url(String),
+    Weighted { url: String, weight: u32 },
+}
+
+fn default_rpc_cooldown() -> u64 {
+    30
+}
+
+fn default_rpc_health_check_interval() -> u64 {
+    60
+}
+
+/// Reads a list of endpoints, or a single one as a list of one.
+fn one_or_many<'de, D>(deserializer: D) -> std::result::Result<Vec<RpcEndpoint>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(RpcEndpoint),
+        Many(Vec<RpcEndpoint>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(endpoint) => vec![endpoint],
+        OneOrMany::Many(endpoints) => endpoints,
+    })
+}
+
+impl RpcEndpoint {
+    pub fn url(&self) -> &str {
+        match self {
+            RpcEndpoint::Url(url) | RpcEndpoint::Weighted { url, .. } => url,
+        }
+    }
+
+    pub fn weight(&self) -> u32 {
+        match self {
+            RpcEndpoint::Url(_) => 1,
+            RpcEndpoint::Weighted { weight, .. } => *weight,
+        }
+    }
+}
+
+/// How the fees of the transactions sent on a chain are picked.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct FeePolicy {
+    /// Used unless the transaction asks for a specific type.
+    pub tx_type: FeeTxType,
+    /// Applied to the estimated max fee per gas and priority fee, or to the gas price.
+    pub multiplier: f64,
+    /// Priority fee in gwei used instead of the estimated one.
+    pub priority_fee: Option<f64>,
+    /// The highest max fee per gas, or gas price, in gwei that is paid. Unlimited if not set.
+    pub max_fee: Option<f64>,
+    /// Seconds to wait for the network fee to drop below `max_fee` before giving up.
+    pub max_fee_wait: u64,
+    /// Seconds to wait for a receipt before the transaction is considered stuck. Zero waits
+    /// indefinitely.
+    pub receipt_timeout: u64,
+    /// What is sent with the same nonce when a transaction is stuck.
+    pub stuck_tx: StuckTxAction,
+    /// How many times a stuck transaction is replaced before giving up.
+    pub max_replacements: u32,
+    /// Percentage by which the fees of a replacement are raised, nodes usually require 10.
+    pub fee_bump: u32,
+    /// Native balance, in MON or ETH, that is never spent so that it's left for gas.
+    #[serde(default)]
+    pub gas_reserve: f64,
+}
+
+impl FeePolicy {
+    const WEI_PER_GWEI: f64 = 1e9;
+    const WEI_PER_ETHER: f64 = 1e18;
+
+    pub fn priority_fee_wei(&self) -> Option<u128> {
+        self.priority_fee.map(|fee| (fee * Self::WEI_PER_GWEI) as u128)
+    }
+
+    pub fn max_fee_wei(&self) -> Option<u128> {
+        self.max_fee.map(|fee| (fee * Self::WEI_PER_GWEI) as u128)
+    }
+
+    pub fn gas_reserve_wei(&self) -> U256 {
+        U256::from((self.gas_reserve * Self::WEI_PER_ETHER) as u128)
+    }
+
+    pub fn receipt_timeout(&self) -> Option<Duration> {
+        match self.receipt_timeout {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    /// Raises a fee by `fee_bump` percent.
+    pub fn bump(&self, fee: u128) -> u128 {
+        fee * (100 + self.fee_bump as u128) / 100
+    }
+}
+
+impl Default for FeePolicy {
+    fn default() -> Self {
+        Self {
+            tx_type: FeeTxType::default(),
+            multiplier: 1.0,
+            priority_fee: None,
+            max_fee: None,
+            max_fee_wait: 0,
+            receipt_timeout: 0,
+            stuck_tx: StuckTxAction::default(),
+            max_replacements: 0,
+            fee_bump: 10,
+            gas_reserve: 0.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StuckTxAction {
+    /// The same transaction with raised fees.
+    #[default]
+    Replace,
+    /// A zero-value transfer to self with raised fees.
+    Cancel,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum FeeTxType {
+    Legacy,
+    #[default]
+    Eip1559,
+}
+
+impl From<FeeTxType> for TxType {
+    fn from(value: FeeTxType) -> Self {
+        match value {
+            FeeTxType::Legacy => TxType::Legacy,
+            FeeTxType::Eip1559 => TxType::Eip1559,
+        }
+    }
+}
+
+/// How many failures in a row of each class an account gets through before it's given up on.
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// The longest wait in seconds before an account tries again. The wait starts at
+    /// `restart_thread_delay` and doubles with every failure in a row.
+    pub max_delay: u64,
+    pub transient_rpc: u32,
+    pub transient_db: u32,
+    pub rate_limit: u32,
+    pub insufficient_balance: u32,
+    pub no_available_actions: u32,
+    pub revert: u32,
+    pub api: u32,
+    pub permanent: u32,
+}
+
+impl RetryPolicy {
+    pub fn max_retries(&self, class: ErrorClass) -> u32 {
+        match class {
+            ErrorClass::TransientRpc => self.transient_rpc,
+            ErrorClass::TransientDb => self.transient_db,
+            ErrorClass::RateLimit => self.rate_limit,
+            ErrorClass::InsufficientBalance => self.insufficient_balance,
+            ErrorClass::NoAvailableActions => self.no_available_actions,
+            ErrorClass::Revert => self.revert,
+            ErrorClass::Api => self.api,
+            ErrorClass::Permanent => self.permanent,
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_delay: 600,
+            transient_rpc: 10,
+            transient_db: 10,
+            rate_limit: 20,
+            insufficient_balance: 0,
+            no_available_actions: 5,
+            revert: 3,
+            api: 5,
+            permanent: 0,
+        }
+    }
+}
+
+/// Selects how the client picks the nonce of a transaction.
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum NonceManagerKind {
+    /// The confirmed transaction count, queried before every transaction.
+    #[default]
+    Strict,
+    /// The transaction count including the mempool, queried before every transaction.
+    Pending,
+    /// Tracked in memory, resynced with the pending count after a failed broadcast.
+    Cached,
 }
 
 impl Config {
     const PATH: &str = "data/config.toml";
 
+    pub fn slippage(&self, dex: Dex) -> Slippage {
+        match dex {
+            Dex::Ambient => self.ambient_slippage,
+            Dex::Bean => self.bean_slippage,
+            Dex::Hashflow => self.hashflow_slippage,
+        }
+    }
+
+    /// Seconds to wait before an account tries again after `failures` failures in a row.
+    pub fn retry_delay(&self, failures: u32) -> u64 {
+        let factor = 1u64 << failures.saturating_sub(1).min(16);
+        self.restart_thread_delay.saturating_mul(factor).min(self.retry_policy.max_delay)
+    }
+
+    /// How often the RPCs are checked, `None` if they aren't.
+    pub fn rpc_health_check_interval(&self) -> Option<Duration> {
+        match self.rpc_health_check_interval {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self> {
+        let config: Self = toml::from_str(s)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks the settings that the types alone don't constrain.
+    fn validate(&self) -> std::result::Result<(), ConfigError> {
+        let slippages = [
+            ("ambient_slippage", self.ambient_slippage),
+            ("bean_slippage", self.bean_slippage),
+            ("hashflow_slippage", self.hashflow_slippage),
+        ];
+
+        for (setting, slippage) in slippages {
+            for (field, value) in [
+                ("slippage_bps", slippage.slippage_bps),
+                ("max_price_impact_bps", slippage.max_price_impact_bps),
+            ] {
+                if value > 10_000 {
+                    return Err(ConfigError::BpsOutOfRange { setting, field, value });
+                }
+            }
+        }
+
+        if self.hashflow_slippage.slippage_bps != 0 {
+            return Err(ConfigError::FirmQuoteSlippage(self.hashflow_slippage.slippage_bps));
+        }
+
+        for (setting, max_tps) in
+            [("monad_max_tps", self.monad_max_tps), ("base_max_tps", self.base_max_tps)]
+        {
+            // the limiter spaces the transactions `1 / max_tps` seconds apart
+            if let Some(value) = max_tps &&
+                Duration::try_from_secs_f64(1.0 / value).is_err()
+            {
+                return Err(ConfigError::InvalidMaxTps { setting, value });
+            }
+        }
+
+        Ok(())
+    }
+
     async fn read_from_file(path: impl AsRef<Path>) -> Result<Self> {
         let cfg_str = tokio::fs::read_to_string(path).await?;
-        Ok(toml::from_str(&cfg_str)?)
+        Self::parse(&cfg_str)
     }
 
     pub async fn read_default() -> Self {
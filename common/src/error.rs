@@ -1,7 +1,15 @@
 use alloy::primitives::ruint::FromUintError;
+use strum::Display;
 use thiserror::Error;
 
-use crate::onchain::dapps::{ambient::AmbientError, bean::BeanError};
+use crate::{
+    config::ConfigError,
+    onchain::{
+        action::ActionError,
+        dapps::{ambient::AmbientError, bean::BeanError, common::SwapError},
+        token::TokenError,
+    },
+};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -16,6 +24,18 @@ pub enum Error {
     #[error(transparent)]
     Ambient(#[from] AmbientError),
 
+    #[error(transparent)]
+    Swap(#[from] SwapError),
+
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+
+    #[error(transparent)]
+    Action(#[from] ActionError),
+
+    #[error(transparent)]
+    Token(#[from] TokenError),
+
     // externals
     #[error(transparent)]
     FromUintToU128(#[from] FromUintError<u128>),
@@ -28,4 +48,57 @@ pub enum Error {
 
     #[error(transparent)]
     Toml(#[from] toml::de::Error),
+
+    #[error(transparent)]
+    UrlParse(#[from] url::ParseError),
+}
+
+/// The kind of a failure, which decides how often an account tries again after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum ErrorClass {
+    /// The RPC couldn't be reached or failed to answer, or the network is congested.
+    TransientRpc,
+    /// The database was busy, e.g. locked by another account writing to it, or its connection
+    /// failed.
+    TransientDb,
+    /// The RPC or a dapp API turned the request down for going over its rate limit.
+    RateLimit,
+    /// The wallet can't pay for the transaction.
+    InsufficientBalance,
+    /// None of the actions left for the account can run right now, e.g. the balance covers none
+    /// of them.
+    NoAvailableActions,
+    /// The transaction reverted, or would have.
+    Revert,
+    /// The HTTP API of a dapp failed or answered with something unexpected.
+    Api,
+    /// Trying again won't help, e.g. the calldata can't be built or the tokens aren't supported.
+    Permanent,
+}
+
+impl Error {
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            Error::EvmClient(e) => e.class(),
+            Error::Bean(_) => ErrorClass::Revert,
+            Error::Swap(SwapError::SlippageOutOfRange(_)) => ErrorClass::Permanent,
+            Error::Swap(SwapError::NoQuote) => ErrorClass::Api,
+            Error::Swap(_) => ErrorClass::Revert,
+            Error::Action(ActionError::EmptyWallet(_)) => ErrorClass::InsufficientBalance,
+            Error::Action(ActionError::UnwrapFailed(_)) => ErrorClass::Revert,
+            Error::Request(e) if e.status().is_some_and(|s| s.as_u16() == 429) => {
+                ErrorClass::RateLimit
+            }
+            Error::Request(_) => ErrorClass::Api,
+            Error::Ambient(_) |
+            Error::Config(_) |
+            Error::Action(_) |
+            Error::Token(_) |
+            Error::FromUintToU128(_) |
+            Error::Io(_) |
+            Error::Toml(_) |
+            Error::UrlParse(_) => ErrorClass::Permanent,
+        }
+    }
 }
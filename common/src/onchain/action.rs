@@ -0,0 +1,424 @@
+use alloy::{
+    network::Ethereum,
+    primitives::{
+        Address, TxHash, U256,
+        utils::{UnitsError, format_ether, format_units},
+    },
+    providers::Provider,
+};
+use alloy_chains::NamedChain;
+use async_trait::async_trait;
+use rand::seq::IndexedRandom;
+use reqwest::Client as ReqwestClient;
+use std::fmt::Display;
+use thiserror::Error;
+
+use crate::{
+    Result,
+    config::Config,
+    onchain::{
+        client::{Client, ClientNonceManager},
+        dapps::{
+            ambient::AmbientSwap,
+            apriori::{AprioriDeposit, AprioriWithdraw},
+            bean::BeanSwap,
+            common::ONE_HUNDRED,
+            gas_zip::GasZipBridge,
+            hashflow::HashflowSwap,
+            kinza::{KinzaDeposit, KinzaWithdraw},
+            nad_domains::NadDomainsMint,
+            shmonad::{ShmonadDeposit, ShmonadRedeem},
+            wmon::{self, WmonUnwrap, WmonWrap},
+        },
+        outcome::TxOutcome,
+        token::Token,
+    },
+    state::Dex,
+    utils::random::random_in_range,
+};
+
+#[derive(Error, Debug)]
+pub enum ActionError {
+    #[error("no non-zero tokens at `{0}`")]
+    EmptyWallet(Address),
+
+    #[error(transparent)]
+    FormatUnits(#[from] UnitsError),
+
+    #[error("no token to swap `{0}` to")]
+    NoSwapTarget(Token),
+
+    #[error("unwrapping WMON before a swap failed in `{0}`")]
+    UnwrapFailed(TxHash),
+}
+
+/// What an action runs with: the client of the chain it asked for, an http client behind the
+/// account's proxy and the config.
+pub struct ActionContext<'a, P, N>
+where
+    P: Provider<Ethereum>,
+{
+    pub client: &'a Client<P, N>,
+    pub http_client: &'a ReqwestClient,
+    pub config: &'a Config,
+    /// The native balance the action must leave untouched: the gas reserve of the chain and the
+    /// estimated gas of the action, as found by [`preflight`].
+    pub reserve: U256,
+    /// The confirmed transactions of the account, oldest first, for actions that pick up where an
+    /// earlier one left off, e.g. a withdrawal of a deposit.
+    pub history: &'a [PastTransaction],
+}
+
+impl<P, N> ActionContext<'_, P, N>
+where
+    P: Provider<Ethereum>,
+{
+    /// The hashes of the confirmed transactions of an action, oldest first.
+    pub fn past_hashes(&self, kind: &str, protocol: &str) -> Vec<TxHash> {
+        self.history
+            .iter()
+            .filter(|tx| tx.action == kind && tx.protocol == protocol)
+            .map(|tx| tx.hash)
+            .collect()
+    }
+}
+
+/// A confirmed transaction from the transaction history of the account.
+#[derive(Debug, Clone)]
+pub struct PastTransaction {
+    pub action: String,
+    pub protocol: String,
+    pub hash: TxHash,
+}
+
+/// What an action did, filled in as it goes so that it's known even if the action fails halfway
+/// through.
+#[derive(Debug, Default, Clone)]
+pub struct ActionRecord {
+    pub token_in: Option<Token>,
+    pub token_out: Option<Token>,
+    pub amount_in: Option<U256>,
+    /// The transactions sent before the one the action returns, oldest first, e.g. an approval.
+    pub sent: Vec<SentTx>,
+}
+
+impl ActionRecord {
+    /// Adds the approval of `amount` of `token` to the sent transactions, if one was needed.
+    /// Returns whether the transaction it's for can go ahead, see [`TxOutcome::blocks_next`].
+    pub fn approved(
+        &mut self,
+        protocol: impl Display,
+        token: Option<Token>,
+        amount: U256,
+        approval: Option<TxOutcome>,
+    ) -> bool {
+        let Some(outcome) = approval else { return true };
+        let blocks_next = outcome.blocks_next();
+
+        self.sent.push(SentTx {
+            kind: "approve",
+            protocol: protocol.to_string(),
+            token_in: token,
+            token_out: None,
+            amount_in: Some(amount),
+            outcome,
+        });
+
+        !blocks_next
+    }
+}
+
+/// A transaction an action sent on the way to the one it returns, which is written to the
+/// transaction history as well.
+#[derive(Debug, Clone)]
+pub struct SentTx {
+    /// The kind as it's stored in the transaction history, e.g. `approve`.
+    pub kind: &'static str,
+    pub protocol: String,
+    pub token_in: Option<Token>,
+    pub token_out: Option<Token>,
+    pub amount_in: Option<U256>,
+    pub outcome: TxOutcome,
+}
+
+/// A single warmup step of a protocol, e.g. a swap on a dex.
+///
+/// The warmup picks an action whose kind and protocol match a goal the account hasn't reached
+/// yet, so a protocol is plugged in by implementing this trait and adding it to [`registry`].
+#[async_trait]
+pub trait Action<P, N>: Send + Sync
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    /// The kind of the action as it's stored in the goals and the transaction history, e.g.
+    /// `swap`.
+    fn kind(&self) -> &'static str;
+
+    /// The protocol as it's stored in the goals and the transaction history, e.g. `AMBIENT`.
+    fn protocol(&self) -> String;
+
+    /// The chain whose client the action gets.
+    fn chain(&self) -> NamedChain {
+        NamedChain::MonadTestnet
+    }
+
+    /// The most gas the action uses, every transaction it sends included.
+    fn gas_limit(&self) -> u64;
+
+    /// The native value the action sends on top of the gas, e.g. a mint price. Actions that
+    /// send a share of the balance leave it at zero and spend what's above
+    /// [`ActionContext::reserve`].
+    fn required_value(&self, _config: &Config) -> U256 {
+        U256::ZERO
+    }
+
+    /// Whether the action can run right now, e.g. the wallet has something to swap.
+    async fn is_available(&self, _ctx: &ActionContext<'_, P, N>) -> Result<bool> {
+        Ok(true)
+    }
+
+    /// Runs the action, writing what it does to `record`. Returns `None` if nothing was sent,
+    /// or if the action stopped at a transaction in [`ActionRecord::sent`], e.g. an approval
+    /// that failed.
+    async fn execute(
+        &self,
+        ctx: &ActionContext<'_, P, N>,
+        record: &mut ActionRecord,
+    ) -> Result<Option<TxOutcome>>;
+}
+
+/// Every action the warmup can run.
+pub fn registry<P, N>() -> Vec<Box<dyn Action<P, N>>>
+where
+    P: Provider<Ethereum> + 'static,
+    N: ClientNonceManager<P> + 'static,
+{
+    vec![
+        Box::new(AmbientSwap),
+        Box::new(BeanSwap),
+        Box::new(HashflowSwap),
+        Box::new(AprioriDeposit),
+        Box::new(KinzaDeposit),
+        Box::new(ShmonadDeposit),
+        Box::new(NadDomainsMint),
+        Box::new(GasZipBridge),
+        Box::new(AprioriWithdraw),
+        Box::new(KinzaWithdraw),
+        Box::new(ShmonadRedeem),
+        Box::new(WmonWrap),
+        Box::new(WmonUnwrap),
+    ]
+}
+
+/// Checks that the native balance covers the value the action sends and its gas at the current
+/// fees, with the gas reserve of the chain left over.
+///
+/// Returns the [`ActionContext::reserve`] the action runs with, or `None` if the balance falls
+/// short and the action shouldn't be sent.
+pub async fn preflight<P, N>(
+    action: &dyn Action<P, N>,
+    client: &Client<P, N>,
+    config: &Config,
+) -> Result<Option<U256>>
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    let reserve = client.gas_reserve() + client.estimate_gas_cost(action.gas_limit()).await?;
+    let required = action.required_value(config) + reserve;
+    let balance = client.get_native_balance().await?;
+
+    if balance < required {
+        tracing::info!(
+            "{} | Skipping the {}, the balance of {} doesn't cover the {} it needs with gas and \
+             the reserve",
+            action.protocol(),
+            action.kind(),
+            format_ether(balance),
+            format_ether(required)
+        );
+        return Ok(None);
+    }
+
+    Ok(Some(reserve))
+}
+
+/// The context an action runs with if it passes the [`preflight`] and is available right now.
+pub async fn prepare<'a, P, N>(
+    action: &dyn Action<P, N>,
+    client: &'a Client<P, N>,
+    http_client: &'a ReqwestClient,
+    config: &'a Config,
+    history: &'a [PastTransaction],
+) -> Result<Option<ActionContext<'a, P, N>>>
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    let Some(reserve) = preflight(action, client, config).await? else { return Ok(None) };
+    let ctx = ActionContext { client, http_client, config, reserve, history };
+
+    match action.is_available(&ctx).await? {
+        true => Ok(Some(ctx)),
+        false => Ok(None),
+    }
+}
+
+/// The native balance above [`ActionContext::reserve`].
+pub async fn spendable_native_balance<P, N>(ctx: &ActionContext<'_, P, N>) -> Result<U256>
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    Ok(ctx.client.get_native_balance().await?.saturating_sub(ctx.reserve))
+}
+
+/// The token a balance is swapped as: WMON isn't traded by the dexes, so it's unwrapped and
+/// swapped as MON.
+fn swapped_as(token: Token) -> Token {
+    match token {
+        Token::WMON => Token::MON,
+        token => token,
+    }
+}
+
+/// The non-zero balances of the tokens the dex can swap, MON counted above the reserve.
+async fn swappable_balances<P, N>(
+    dex: Dex,
+    ctx: &ActionContext<'_, P, N>,
+) -> Result<Vec<(Token, U256)>>
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    let mut balances = ctx.client.get_nonzero_token_balances().await?;
+    balances.push((Token::WMON, ctx.client.get_token_balance(Token::WMON.address()).await?));
+
+    for (token, balance) in &mut balances {
+        if token.is_native() {
+            *balance = balance.saturating_sub(ctx.reserve);
+        }
+    }
+    balances.retain(|(token, balance)| {
+        !balance.is_zero() && swapped_as(*token).is_supported_by(&dex.to_string())
+    });
+
+    Ok(balances)
+}
+
+/// Whether the wallet holds any token the dex can swap.
+pub async fn has_swappable_balance<P, N>(dex: Dex, ctx: &ActionContext<'_, P, N>) -> Result<bool>
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    Ok(!swappable_balances(dex, ctx).await?.is_empty())
+}
+
+/// Picks a random token the wallet holds, a random other token to swap it to and a share of the
+/// balance to swap as set by `swap_ratio`. Only the tokens the dex supports in
+/// `data/tokens.toml` are picked, and only the MON above the reserve is swapped.
+///
+/// A WMON balance is unwrapped first and swapped as MON. The record still shows WMON as the
+/// token in, and the unwrap goes into [`ActionRecord::sent`].
+pub async fn pick_swap<P, N>(
+    dex: Dex,
+    ctx: &ActionContext<'_, P, N>,
+    record: &mut ActionRecord,
+) -> Result<(Token, Token, U256)>
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    let nonzero_tokens = swappable_balances(dex, ctx).await?;
+
+    let (token_in, token_in_balance) = *nonzero_tokens
+        .choose(&mut rand::rng())
+        .ok_or_else(|| ActionError::EmptyWallet(ctx.client.address()))?;
+
+    let token_out = Token::random_swap_target(swapped_as(token_in), &dex.to_string())
+        .ok_or(ActionError::NoSwapTarget(token_in))?;
+    let ratio = random_in_range(ctx.config.swap_ratio);
+    let amount_in = token_in_balance * U256::from(ratio) / ONE_HUNDRED;
+
+    record.token_in = Some(token_in);
+    record.token_out = Some(token_out);
+    record.amount_in = Some(amount_in);
+
+    tracing::info!(
+        "{dex} | Swapping {} {token_in} to {token_out}",
+        format_units(amount_in, token_in.decimals()).map_err(ActionError::FormatUnits)?
+    );
+
+    if token_in == Token::WMON {
+        let outcome = wmon::unwrap(ctx.client, amount_in).await?;
+        let (success, hash) = (outcome.is_success(), outcome.hash);
+        record.sent.push(SentTx {
+            kind: "unwrap",
+            protocol: wmon::WMON_PROTOCOL.to_string(),
+            token_in: Some(Token::WMON),
+            token_out: Some(Token::MON),
+            amount_in: Some(amount_in),
+            outcome,
+        });
+        if !success {
+            return Err(ActionError::UnwrapFailed(hash).into());
+        }
+
+        return Ok((Token::MON, token_out, amount_in));
+    }
+
+    Ok((token_in, token_out, amount_in))
+}
+
+/// Picks a share of the MON balance above the reserve to deposit as set by `deposit_ratio`.
+pub async fn pick_deposit<P, N>(
+    protocol: impl Display,
+    ctx: &ActionContext<'_, P, N>,
+    record: &mut ActionRecord,
+) -> Result<U256>
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    let balance = spendable_native_balance(ctx).await?;
+
+    let ratio = random_in_range(ctx.config.deposit_ratio);
+    let amount_in = balance * U256::from(ratio) / ONE_HUNDRED;
+
+    record.token_in = Some(Token::MON);
+    record.amount_in = Some(amount_in);
+
+    tracing::info!(
+        "{protocol} | Depositting {} MON",
+        format_units(amount_in, Token::MON.decimals()).map_err(ActionError::FormatUnits)?
+    );
+
+    Ok(amount_in)
+}
+
+/// Picks the whole balance of the receipt token of a lending position to withdraw as MON.
+pub async fn pick_withdrawal<P, N>(
+    protocol: impl Display,
+    ctx: &ActionContext<'_, P, N>,
+    record: &mut ActionRecord,
+    token: Token,
+) -> Result<U256>
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    let amount_in = ctx.client.get_token_balance(token.address()).await?;
+
+    record.token_in = Some(token);
+    record.token_out = Some(Token::MON);
+    record.amount_in = Some(amount_in);
+
+    tracing::info!(
+        "{protocol} | Withdrawing {} {token}",
+        format_units(amount_in, token.decimals()).map_err(ActionError::FormatUnits)?
+    );
+
+    Ok(amount_in)
+}
@@ -2,18 +2,28 @@ use alloy::{
     consensus::{SignableTransaction, TxEnvelope, TxType, TypedTransaction},
     hex::encode_prefixed,
     network::{Ethereum, ReceiptResponse, TransactionBuilder, TxSigner},
-    primitives::{Address, U256},
-    providers::{MulticallBuilder, Provider},
-    rpc::types::TransactionRequest,
+    primitives::{Address, TxHash, U256},
+    providers::{
+        MulticallBuilder, PendingTransactionBuilder, PendingTransactionError, Provider,
+        WatchTxError, utils::Eip1559Estimation,
+    },
+    rpc::types::{BlockId, TransactionReceipt, TransactionRequest},
     signers::{Signer, local::PrivateKeySigner},
     sol,
-    sol_types::SolCall,
+    sol_types::{SolCall, decode_revert_reason},
 };
 use alloy_chains::Chain;
-use strum::IntoEnumIterator;
+use std::time::Duration;
+use tokio::{sync::Mutex, time::Instant};
 
-use super::{error::ClientError, token::Token};
-use crate::Result;
+use super::{error::ClientError, outcome::TxOutcome, rate_limit::TxRateLimiter, token::Token};
+use crate::{
+    Result,
+    config::{FeePolicy, StuckTxAction},
+};
+
+/// How often the fees are estimated again while they are above the cap.
+const FEE_POLL_INTERVAL: Duration = Duration::from_secs(15);
 
 sol! {
     #[sol(rpc)]
@@ -34,15 +44,44 @@ where
     pub provider: P,
     pub signer: PrivateKeySigner,
     nonce_manager: N,
+    fee_policy: FeePolicy,
+    simulate: bool,
+    rate_limiter: Option<TxRateLimiter>,
 }
 
 impl<P, N> Client<P, N>
 where
     P: Provider<Ethereum>,
-    N: Default + ClientNonceManager<P>,
+    N: ClientNonceManager<P>,
 {
     pub fn new(signer: PrivateKeySigner, chain: Chain, provider: P) -> Self {
-        Self { chain, provider, signer, nonce_manager: N::default() }
+        Self {
+            chain,
+            provider,
+            signer,
+            nonce_manager: N::default(),
+            fee_policy: FeePolicy::default(),
+            simulate: false,
+            rate_limiter: None,
+        }
+    }
+
+    pub fn with_fee_policy(mut self, fee_policy: FeePolicy) -> Self {
+        self.fee_policy = fee_policy;
+        self
+    }
+
+    /// Makes [`Self::send_transaction`] simulate transactions instead of broadcasting them.
+    pub fn with_simulation(mut self, simulate: bool) -> Self {
+        self.simulate = simulate;
+        self
+    }
+
+    /// Makes every broadcast, replacements included, wait for a slot of the limiter, which is
+    /// usually shared with the other clients of the chain.
+    pub fn with_rate_limiter(mut self, rate_limiter: Option<TxRateLimiter>) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
     }
 
     pub fn address(&self) -> Address {
@@ -67,70 +106,345 @@ where
         }
     }
 
-    pub async fn send_transaction(
+    /// Fills in the nonce, fees and gas limit, then signs and broadcasts the transaction.
+    ///
+    /// Returns the filled request along with the pending transaction.
+    async fn broadcast(
         &self,
         tx: TransactionRequest,
         tx_type: Option<TxType>,
-    ) -> Result<bool> {
+    ) -> Result<(TransactionRequest, PendingTransactionBuilder<Ethereum>)> {
         let mut tx = tx
             .with_from(self.signer.address())
             .with_nonce(self.nonce_manager.get_next_nonce(self).await?)
             .with_chain_id(self.chain.id());
 
-        let tx_type = tx_type.unwrap_or(TxType::Eip1559);
+        let tx_type = tx_type.unwrap_or(self.fee_policy.tx_type.into());
+        let fee = self.estimate_fees_within_cap(tx_type).await?;
+
+        match tx_type {
+            TxType::Legacy => tx.set_gas_price(fee.max_fee_per_gas),
+            _ => {
+                tx.set_max_fee_per_gas(fee.max_fee_per_gas);
+                tx.set_max_priority_fee_per_gas(fee.max_priority_fee_per_gas);
+            }
+        };
+
+        let gas = self.provider.estimate_gas(&tx).await.map_err(ClientError::Rpc)?;
+        tx.set_gas_limit(gas);
+
+        let pending = self.sign_and_send(tx.clone()).await?;
+
+        Ok((tx, pending))
+    }
+
+    async fn sign_and_send(
+        &self,
+        tx: TransactionRequest,
+    ) -> Result<PendingTransactionBuilder<Ethereum>> {
+        let envelope = self.sign_tx_request(tx).await?;
+
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire().await;
+        }
+
+        let pending = self.provider.send_tx_envelope(envelope).await.map_err(ClientError::Rpc)?;
+        Ok(pending)
+    }
+
+    /// Builds the transaction that takes the place of a stuck one: the same transaction, or a
+    /// zero-value transfer to self, with the same nonce and fees raised by the fee policy.
+    ///
+    /// Returns None if the raised fees would be above the cap.
+    fn replacement(&self, stuck: &TransactionRequest) -> Option<TransactionRequest> {
+        let mut tx = match self.fee_policy.stuck_tx {
+            StuckTxAction::Replace => stuck.clone(),
+            StuckTxAction::Cancel => {
+                let mut cancel = stuck
+                    .clone()
+                    .with_to(self.address())
+                    .with_value(U256::ZERO)
+                    .with_input(Vec::new())
+                    .with_gas_limit(21_000);
+                cancel.access_list = None;
+                cancel
+            }
+        };
+
+        let max_fee = match (stuck.gas_price, stuck.max_fee_per_gas) {
+            (Some(gas_price), _) => {
+                let gas_price = self.fee_policy.bump(gas_price);
+                tx.set_gas_price(gas_price);
+                gas_price
+            }
+            (None, Some(max_fee_per_gas)) => {
+                let max_fee_per_gas = self.fee_policy.bump(max_fee_per_gas);
+                let max_priority_fee_per_gas =
+                    self.fee_policy.bump(stuck.max_priority_fee_per_gas.unwrap_or_default());
+                tx.set_max_fee_per_gas(max_fee_per_gas);
+                tx.set_max_priority_fee_per_gas(max_priority_fee_per_gas);
+                max_fee_per_gas
+            }
+            (None, None) => return None,
+        };
+
+        match self.fee_policy.max_fee_wei() {
+            Some(cap) if max_fee > cap => None,
+            _ => Some(tx),
+        }
+    }
+
+    /// Returns the receipt of the first of the transactions that made it into a block.
+    async fn find_receipt(&self, hashes: &[TxHash]) -> Result<Option<TransactionReceipt>> {
+        for hash in hashes {
+            let receipt =
+                self.provider.get_transaction_receipt(*hash).await.map_err(ClientError::Rpc)?;
+            if receipt.is_some() {
+                return Ok(receipt);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Waits for the transaction to be mined, replacing it while it's stuck.
+    ///
+    /// Returns the receipt along with the hashes of every transaction sent with its nonce.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClientError::StuckTx`] if no attempt was mined after `max_replacements`
+    /// replacements, or if a replacement would exceed the fee cap.
+    async fn wait_for_receipt(
+        &self,
+        tx: TransactionRequest,
+        mut pending: PendingTransactionBuilder<Ethereum>,
+    ) -> Result<(TransactionReceipt, Vec<TxHash>)> {
+        let timeout = self.fee_policy.receipt_timeout();
+        let nonce = tx.nonce.unwrap_or_default();
+        let mut hashes = vec![*pending.tx_hash()];
+        let mut last = tx;
+
+        loop {
+            match pending.with_timeout(timeout).get_receipt().await {
+                Ok(receipt) => return Ok((receipt, hashes)),
+                Err(PendingTransactionError::TxWatcher(WatchTxError::Timeout)) => {}
+                Err(e) => return Err(ClientError::PendingTx(e).into()),
+            }
+
+            // an earlier attempt may have been mined while waiting for a later one
+            if let Some(receipt) = self.find_receipt(&hashes).await? {
+                return Ok((receipt, hashes));
+            }
+
+            let attempts = hashes.len() as u32;
+            let stuck = ClientError::StuckTx { nonce, attempts };
+
+            let replacement = match self.replacement(&last) {
+                Some(tx) if attempts <= self.fee_policy.max_replacements => tx,
+                _ => return Err(stuck.into()),
+            };
+
+            tracing::warn!(
+                "Transaction {} with nonce {nonce} is not mined yet, sending replacement {attempts}",
+                hashes.last().unwrap()
+            );
+
+            pending = match self.sign_and_send(replacement.clone()).await {
+                Ok(pending) => pending,
+                // the nonce is most likely taken by an attempt that got mined just now
+                Err(e) => match self.find_receipt(&hashes).await? {
+                    Some(receipt) => return Ok((receipt, hashes)),
+                    None => return Err(e),
+                },
+            };
+
+            hashes.push(*pending.tx_hash());
+            last = replacement;
+        }
+    }
+
+    /// Estimates the fees and applies the multiplier and priority fee of the fee policy.
+    ///
+    /// For legacy transactions `max_fee_per_gas` holds the gas price.
+    async fn estimate_fees(&self, tx_type: TxType) -> Result<Eip1559Estimation> {
+        let policy = &self.fee_policy;
+        let scale = |fee: u128| (fee as f64 * policy.multiplier) as u128;
 
         match tx_type {
             TxType::Legacy => {
                 let gas_price = self.provider.get_gas_price().await.map_err(ClientError::Rpc)?;
-                tx.set_gas_price(gas_price);
+                Ok(Eip1559Estimation {
+                    max_fee_per_gas: scale(gas_price),
+                    max_priority_fee_per_gas: 0,
+                })
             }
             TxType::Eip1559 => {
                 let fee =
                     self.provider.estimate_eip1559_fees(None).await.map_err(ClientError::Rpc)?;
-                tx.set_max_fee_per_gas(fee.max_fee_per_gas);
-                tx.set_max_priority_fee_per_gas(fee.max_priority_fee_per_gas);
+                let max_priority_fee_per_gas = policy
+                    .priority_fee_wei()
+                    .unwrap_or_else(|| scale(fee.max_priority_fee_per_gas));
+                Ok(Eip1559Estimation {
+                    max_fee_per_gas: scale(fee.max_fee_per_gas).max(max_priority_fee_per_gas),
+                    max_priority_fee_per_gas,
+                })
             }
-            _ => {
-                return Err(crate::Error::EvmClient(ClientError::UnexpectedTxType(tx_type)));
+            _ => Err(crate::Error::EvmClient(ClientError::UnexpectedTxType(tx_type))),
+        }
+    }
+
+    /// Estimates the fees, waiting for them to drop while they are above the cap of the fee
+    /// policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClientError::FeeAboveCap`] if the fees are still above the cap once
+    /// `max_fee_wait` seconds have passed.
+    async fn estimate_fees_within_cap(&self, tx_type: TxType) -> Result<Eip1559Estimation> {
+        let deadline = Instant::now() + Duration::from_secs(self.fee_policy.max_fee_wait);
+
+        loop {
+            let fee = self.estimate_fees(tx_type).await?;
+
+            let cap = match self.fee_policy.max_fee_wei() {
+                Some(cap) if fee.max_fee_per_gas > cap => cap,
+                _ => return Ok(fee),
+            };
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(ClientError::FeeAboveCap { fee: fee.max_fee_per_gas, cap }.into());
             }
-        };
 
-        let gas = self.provider.estimate_gas(&tx).await.map_err(ClientError::Rpc)?;
-        tx.set_gas_limit(gas);
+            tracing::warn!(
+                "Network fee of {} wei is above the cap of {cap} wei, waiting for it to drop",
+                fee.max_fee_per_gas
+            );
+            tokio::time::sleep(FEE_POLL_INTERVAL.min(deadline - now)).await;
+        }
+    }
 
-        let envelope = self.sign_tx_request(tx).await?;
+    /// Signs and broadcasts the transaction, then waits for its receipt.
+    ///
+    /// A reverted transaction is not an error: the returned [`TxOutcome`] has `success` set to
+    /// false and carries the revert reason when it can be decoded.
+    pub async fn send_transaction(
+        &self,
+        tx: TransactionRequest,
+        tx_type: Option<TxType>,
+    ) -> Result<TxOutcome> {
+        if self.simulate {
+            return self.simulate_transaction(tx).await;
+        }
 
-        let receipt = self
-            .provider
-            .send_tx_envelope(envelope)
-            .await
-            .map_err(ClientError::Rpc)?
-            .get_receipt()
-            .await
-            .map_err(ClientError::PendingTx)?;
+        let (tx, pending) = match self.broadcast(tx, tx_type).await {
+            Ok(res) => res,
+            Err(e) => {
+                // covers "nonce too low" and "already known" as well as a nonce that was taken
+                // but never sent, the next transaction has to start from the chain state again
+                self.nonce_manager.resync().await;
+                return Err(e);
+            }
+        };
 
-        let (_, url) = self.chain.etherscan_urls().unwrap_or(("", ""));
+        let (receipt, hashes) = match self.wait_for_receipt(tx.clone(), pending).await {
+            Ok(res) => res,
+            Err(e) => {
+                // the stuck transaction may still be mined, the nonce can't be trusted anymore
+                self.nonce_manager.resync().await;
+                return Err(e);
+            }
+        };
 
-        let status = receipt.status();
+        let (_, url) = self.chain.etherscan_urls().unwrap_or(("", ""));
         let tx_hash = format!("{url}/tx/{}", receipt.transaction_hash());
 
-        match status {
-            true => {
-                tracing::info!("Transaction successful: {tx_hash}")
+        // every attempt after the first one is a cancellation with that policy
+        let cancelled = matches!(self.fee_policy.stuck_tx, StuckTxAction::Cancel) &&
+            hashes[0] != receipt.transaction_hash;
+
+        let outcome = match (receipt.status(), cancelled) {
+            (_, true) => {
+                tracing::warn!("Transaction cancelled: {tx_hash}");
+                TxOutcome::from_receipt(&receipt, None)
             }
-            false => {
-                tracing::error!("Transaction failed: {tx_hash}")
+            (true, false) => {
+                tracing::info!("Transaction successful: {tx_hash}");
+                TxOutcome::from_receipt(&receipt, None)
+            }
+            (false, false) => {
+                let reason = self.revert_reason(tx, receipt.block_number).await;
+                tracing::error!(
+                    "Transaction failed: {tx_hash}, reason: {}",
+                    reason.as_deref().unwrap_or("unknown")
+                );
+                TxOutcome::from_receipt(&receipt, reason)
             }
+        };
+
+        Ok(TxOutcome { attempts: hashes.len() as u32, cancelled, ..outcome })
+    }
+
+    /// Estimates the transaction against the latest state without broadcasting it.
+    ///
+    /// A transaction that would revert results in an unsuccessful outcome with the reason the
+    /// node gave.
+    async fn simulate_transaction(&self, tx: TransactionRequest) -> Result<TxOutcome> {
+        let tx = tx.with_from(self.signer.address()).with_chain_id(self.chain.id());
+        let gas_price = self.provider.get_gas_price().await.map_err(ClientError::Rpc)?;
+
+        let (success, gas_used, revert_reason) = match self.provider.estimate_gas(&tx).await {
+            Ok(gas) => (true, gas, None),
+            Err(e) => match e.as_error_resp() {
+                Some(payload) => {
+                    let reason = payload
+                        .as_revert_data()
+                        .and_then(|data| decode_revert_reason(&data))
+                        .unwrap_or_else(|| payload.message.to_string());
+                    (false, 0, Some(reason))
+                }
+                None => return Err(ClientError::Rpc(e).into()),
+            },
+        };
+
+        match &revert_reason {
+            None => tracing::info!("Simulated transaction would succeed, gas: {gas_used}"),
+            Some(reason) => tracing::warn!("Simulated transaction would fail: {reason}"),
         }
 
-        Ok(true)
+        Ok(TxOutcome {
+            hash: TxHash::ZERO,
+            success,
+            gas_used,
+            effective_gas_price: gas_price,
+            block_number: None,
+            revert_reason,
+            simulated: true,
+            attempts: 1,
+            cancelled: false,
+        })
+    }
+
+    /// Replays a reverted transaction on top of its parent block to recover the revert reason.
+    async fn revert_reason(&self, tx: TransactionRequest, block: Option<u64>) -> Option<String> {
+        let block = block.map(|b| BlockId::number(b.saturating_sub(1))).unwrap_or_default();
+
+        match self.provider.call(&tx).block(block).await {
+            Ok(_) => None,
+            Err(e) => e
+                .as_error_resp()
+                .and_then(|payload| payload.as_revert_data())
+                .and_then(|data| decode_revert_reason(&data)),
+        }
     }
 
     /// Approves a spender to transfer tokens if needed.
     ///
-    /// - Returns Ok(true) immediately if the token is native.
+    /// - Returns Ok(None) immediately if the token is native.
     /// - If ignore_allowance is true, skips the allowance check.
-    /// - Sends an approval tx only if the current allowance is less than the requested amount.
+    /// - Sends an approval tx only if the current allowance is less than the requested amount,
+    ///   returning its outcome.
     ///
     /// # Errors
     ///
@@ -142,12 +456,24 @@ where
         spender: Address,
         amount: U256,
         ignore_allowance: bool,
-    ) -> Result<bool> {
+    ) -> Result<Option<TxOutcome>> {
         if token.is_native() {
-            return Ok(true);
+            return Ok(None);
         }
 
-        let instance = IERC20::new(token.address(), &self.provider);
+        self.approve_address(token.address(), spender, amount, ignore_allowance).await
+    }
+
+    /// Like [`Self::approve`], for a token that isn't a [`Token`], e.g. the receipt token of a
+    /// lending position.
+    pub async fn approve_address(
+        &self,
+        token: Address,
+        spender: Address,
+        amount: U256,
+        ignore_allowance: bool,
+    ) -> Result<Option<TxOutcome>> {
+        let instance = IERC20::new(token, &self.provider);
 
         let allowance = match ignore_allowance {
             true => U256::ZERO,
@@ -165,11 +491,11 @@ where
             true => {
                 let tx = TransactionRequest::default()
                     .with_input(IERC20::approveCall { spender, amount }.abi_encode())
-                    .with_to(token.address());
+                    .with_to(token);
 
-                self.send_transaction(tx, None).await
+                self.send_transaction(tx, None).await.map(Some)
             }
-            false => Ok(true),
+            false => Ok(None),
         }
     }
 
@@ -180,14 +506,44 @@ where
         Ok(signature)
     }
 
+    /// What `gas_limit` gas costs at the current fees of the fee policy.
+    pub async fn estimate_gas_cost(&self, gas_limit: u64) -> Result<U256> {
+        let fee = self.estimate_fees(self.fee_policy.tx_type.into()).await?;
+        Ok(U256::from(fee.max_fee_per_gas) * U256::from(gas_limit))
+    }
+
+    /// The native balance that is never spent, as set by the fee policy.
+    pub fn gas_reserve(&self) -> U256 {
+        self.fee_policy.gas_reserve_wei()
+    }
+
     pub async fn get_native_balance(&self) -> Result<U256> {
         let balance = self.provider.get_balance(self.address()).await.map_err(ClientError::Rpc)?;
         Ok(balance)
     }
 
+    pub async fn get_token_balance(&self, token: Address) -> Result<U256> {
+        let balance = IERC20::new(token, &self.provider)
+            .balanceOf(self.address())
+            .call()
+            .await
+            .map_err(ClientError::Contract)?
+            ._0;
+        Ok(balance)
+    }
+
+    pub async fn get_receipt(&self, hash: TxHash) -> Result<Option<TransactionReceipt>> {
+        let receipt =
+            self.provider.get_transaction_receipt(hash).await.map_err(ClientError::Rpc)?;
+        Ok(receipt)
+    }
+
     pub async fn get_nonzero_token_balances(&self) -> Result<Vec<(Token, U256)>> {
-        let tokens =
-            Token::iter().filter(|t| !t.is_native() && t.is_swap_allowed()).collect::<Vec<_>>();
+        let tokens = Token::all()
+            .filter(|t| {
+                self.chain == Chain::from(t.chain()) && !t.is_native() && t.is_swap_allowed()
+            })
+            .collect::<Vec<_>>();
 
         let builder =
             tokens.iter().fold(MulticallBuilder::new_dynamic(&self.provider), |builder, token| {
@@ -210,11 +566,20 @@ where
     }
 }
 
-#[allow(async_fn_in_trait)]
-pub trait ClientNonceManager<P: Provider>: Default {
-    async fn get_next_nonce(&self, client: &Client<P, Self>) -> Result<u64>;
+/// The futures are `Send`, so that a client can be used behind a `dyn` [`Action`].
+///
+/// [`Action`]: crate::onchain::action::Action
+pub trait ClientNonceManager<P: Provider>: Default + Send + Sync {
+    fn get_next_nonce(&self, client: &Client<P, Self>) -> impl Future<Output = Result<u64>> + Send;
+
+    /// Called when a transaction couldn't be broadcast, so that a manager keeping its own
+    /// state can drop it.
+    fn resync(&self) -> impl Future<Output = ()> + Send {
+        async {}
+    }
 }
 
+/// Asks the node for the confirmed transaction count before every transaction.
 #[derive(Default)]
 pub struct StrictNonceManager;
 
@@ -228,3 +593,51 @@ impl<P: Provider> ClientNonceManager<P> for StrictNonceManager {
         Ok(nonce)
     }
 }
+
+/// Asks the node for the transaction count including its mempool before every transaction.
+#[derive(Default)]
+pub struct PendingNonceManager;
+
+impl<P: Provider> ClientNonceManager<P> for PendingNonceManager {
+    async fn get_next_nonce(&self, client: &Client<P, Self>) -> Result<u64> {
+        let nonce = client
+            .provider
+            .get_transaction_count(client.signer.address())
+            .pending()
+            .await
+            .map_err(ClientError::Rpc)?;
+        Ok(nonce)
+    }
+}
+
+/// Tracks the nonce of the client's signer in memory.
+///
+/// The node is only asked for the pending transaction count on the first transaction and after
+/// a resync, so consecutive transactions such as an approve followed by a swap never race.
+#[derive(Default)]
+pub struct CachedNonceManager {
+    next_nonce: Mutex<Option<u64>>,
+}
+
+impl<P: Provider> ClientNonceManager<P> for CachedNonceManager {
+    async fn get_next_nonce(&self, client: &Client<P, Self>) -> Result<u64> {
+        let mut next_nonce = self.next_nonce.lock().await;
+
+        let nonce = match *next_nonce {
+            Some(nonce) => nonce,
+            None => client
+                .provider
+                .get_transaction_count(client.signer.address())
+                .pending()
+                .await
+                .map_err(ClientError::Rpc)?,
+        };
+
+        *next_nonce = Some(nonce + 1);
+        Ok(nonce)
+    }
+
+    async fn resync(&self) {
+        *self.next_nonce.lock().await = None;
+    }
+}
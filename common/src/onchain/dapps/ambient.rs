@@ -12,13 +12,23 @@ use alloy::{
 };
 use thiserror::Error;
 
+use super::common::{SWAP_GAS_LIMIT, check_price_impact, min_amount_out, probe_amount};
 use crate::{
     Result,
-    onchain::{client::Client, error::ClientError, token::Token},
+    config::Slippage,
+    onchain::{
+        action::{Action, ActionContext, ActionRecord, has_swappable_balance, pick_swap},
+        client::{Client, ClientNonceManager},
+        error::ClientError,
+        outcome::TxOutcome,
+        token::Token,
+    },
+    state::Dex,
 };
+use async_trait::async_trait;
 
 const IMPACT_CA: Address = address!("0x70a6a0C905af5737aD73Ceba4e6158e995031d4B");
-const DEX_CA: Address = address!("0x88B96aF200c8a9c35442C8AC6cd3D22695AaE4F0");
+pub const DEX_CA: Address = address!("0x88B96aF200c8a9c35442C8AC6cd3D22695AaE4F0");
 const POOL_IDX: U256 = U256::from_limbs([36000, 0, 0, 0]);
 const POOL_TIP: u16 = 0;
 
@@ -28,7 +38,7 @@ const SETTLE_FLAGS: u8 = 0;
 
 const CALL_PATH: u16 = 1;
 
-enum Pool {
+pub(crate) enum Pool {
     MonUsdc,
     MonShmon,
     UsdcShmon,
@@ -52,6 +62,8 @@ macro_rules! pool_match {
 }
 
 impl Pool {
+    const ALL: [Pool; 3] = [Pool::MonUsdc, Pool::MonShmon, Pool::UsdcShmon];
+
     const fn quote(&self) -> Token {
         match self {
             Pool::MonUsdc => Token::USDC,
@@ -68,6 +80,15 @@ impl Pool {
         }
     }
 
+    /// Whether one of the pools trades the two tokens, by symbol, so that the registry can be
+    /// checked before it's set.
+    pub(crate) fn exists(symbol_a: &str, symbol_b: &str) -> bool {
+        Self::ALL.iter().any(|pool| {
+            let (base, quote) = (pool.base().ticker(), pool.quote().ticker());
+            (base, quote) == (symbol_a, symbol_b) || (base, quote) == (symbol_b, symbol_a)
+        })
+    }
+
     fn from_tokens(token_a: Token, token_b: Token) -> Option<Self> {
         pool_match! { token_a, token_b;
             (MON, USDC) => MonUsdc,
@@ -112,8 +133,8 @@ sol! {
     }
 }
 
-async fn get_amount_out<P>(
-    client: &Client<P>,
+async fn get_amount_out<P, N>(
+    client: &Client<P, N>,
     base_token: Token,
     quote_token: Token,
     is_buy: bool,
@@ -123,6 +144,7 @@ async fn get_amount_out<P>(
 ) -> Result<i128>
 where
     P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
 {
     let impact_instance = Impact::new(IMPACT_CA, &client.provider);
 
@@ -149,14 +171,16 @@ where
     Ok(amount_out)
 }
 
-async fn build_cmd_data<P>(
-    client: &Client<P>,
+async fn build_cmd_data<P, N>(
+    client: &Client<P, N>,
     token_in: &Token,
     pool: Pool,
     amount: U256,
+    slippage: &Slippage,
 ) -> Result<Vec<u8>>
 where
     P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
 {
     let (is_buy, in_base_qty, limit_price) =
         if *token_in == Token::MON || token_in.address() == pool.base().address() {
@@ -167,12 +191,29 @@ where
 
     let qty = amount.try_into()?;
 
-    let amount_out =
+    let amount_out = U256::from(
         get_amount_out(client, pool.base(), pool.quote(), is_buy, in_base_qty, qty, limit_price)
             .await?
-            .unsigned_abs();
+            .unsigned_abs(),
+    );
+
+    if let Some(probe) = probe_amount(amount) {
+        let probe_amount_out = get_amount_out(
+            client,
+            pool.base(),
+            pool.quote(),
+            is_buy,
+            in_base_qty,
+            probe.try_into()?,
+            limit_price,
+        )
+        .await?
+        .unsigned_abs();
+
+        check_price_impact(amount_out, U256::from(probe_amount_out), slippage)?;
+    }
 
-    let amount_out = amount_out * (100u128 - 1u128) / 100u128;
+    let amount_out = min_amount_out(amount_out, slippage)?.try_into()?;
 
     let cmd = UserCmd {
         base: pool.base().address(),
@@ -191,24 +232,28 @@ where
     Ok(cmd)
 }
 
-pub async fn swap<P>(
-    client: &Client<P>,
+/// Swaps `amount_in` of `token_in` to `token_out`, approving the dex first if needed. The
+/// approval is added to `record`, and `None` is returned if the swap can't go ahead after it.
+pub async fn swap<P, N>(
+    client: &Client<P, N>,
     amount_in: U256,
     token_in: Token,
     token_out: Token,
-) -> Result<bool>
+    slippage: &Slippage,
+    record: &mut ActionRecord,
+) -> Result<Option<TxOutcome>>
 where
     P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
 {
-    let approved = client.approve(token_in, DEX_CA, amount_in, false).await?;
-
-    if !approved {
-        return Ok(false);
+    let approval = client.approve(token_in, DEX_CA, amount_in, false).await?;
+    if !record.approved(Dex::Ambient, Some(token_in), amount_in, approval) {
+        return Ok(None);
     }
 
     let pool = Pool::from_tokens(token_in, token_out)
         .ok_or(AmbientError::UnsupportedTokens(token_in, token_out))?;
-    let cmd = build_cmd_data(client, &token_in, pool, amount_in).await?;
+    let cmd = build_cmd_data(client, &token_in, pool, amount_in, slippage).await?;
     let value = match token_in.is_native() {
         true => amount_in,
         false => U256::ZERO,
@@ -219,5 +264,42 @@ where
         .with_to(DEX_CA)
         .with_value(value);
 
-    client.send_transaction(tx, None).await
+    client.send_transaction(tx, None).await.map(Some)
+}
+
+/// Swaps a share of a random token the wallet holds on Ambient.
+pub struct AmbientSwap;
+
+#[async_trait]
+impl<P, N> Action<P, N> for AmbientSwap
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    fn kind(&self) -> &'static str {
+        "swap"
+    }
+
+    fn protocol(&self) -> String {
+        Dex::Ambient.to_string()
+    }
+
+    fn gas_limit(&self) -> u64 {
+        SWAP_GAS_LIMIT
+    }
+
+    async fn is_available(&self, ctx: &ActionContext<'_, P, N>) -> Result<bool> {
+        has_swappable_balance(Dex::Ambient, ctx).await
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ActionContext<'_, P, N>,
+        record: &mut ActionRecord,
+    ) -> Result<Option<TxOutcome>> {
+        let (token_in, token_out, amount_in) = pick_swap(Dex::Ambient, ctx, record).await?;
+        let slippage = ctx.config.slippage(Dex::Ambient);
+
+        swap(ctx.client, amount_in, token_in, token_out, &slippage, record).await
+    }
 }
@@ -1,25 +1,47 @@
 use crate::{
     Result,
-    onchain::{client::Client as EvmClient, token::Token},
+    onchain::{
+        action::{
+            Action, ActionContext, ActionRecord, pick_deposit, pick_withdrawal,
+            spendable_native_balance,
+        },
+        client::{Client as EvmClient, ClientNonceManager},
+        dapps::common::{DEPOSIT_GAS_LIMIT, WITHDRAW_GAS_LIMIT},
+        error::ClientError,
+        outcome::TxOutcome,
+        token::Token,
+    },
+    state::Lending,
 };
 use alloy::{
     network::{Ethereum, TransactionBuilder},
-    primitives::U256,
+    primitives::{TxHash, U256},
     providers::Provider,
     rpc::types::TransactionRequest,
     sol,
     sol_types::SolCall,
 };
+use async_trait::async_trait;
 
 sol! {
+    #[sol(rpc)]
     interface IApriori {
         function deposit(uint256 assets, address receiver) external payable returns (uint256);
+
+        function requestRedeem(uint256 shares, address controller, address owner) external returns (uint256);
+
+        function redeem(uint256[] requestIds, address receiver) external returns (uint256);
+
+        function claimableRedeemRequest(uint256 requestId, address controller) external view returns (uint256);
+
+        event RedeemRequest(address indexed controller, address indexed owner, uint256 indexed requestId, address sender, uint256 shares);
     }
 }
 
-pub async fn deposit<P>(evm_client: &EvmClient<P>, amount: U256) -> Result<bool>
+pub async fn deposit<P, N>(evm_client: &EvmClient<P, N>, amount: U256) -> Result<TxOutcome>
 where
     P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
 {
     let tx = TransactionRequest::default()
         .with_input(
@@ -31,3 +53,176 @@ where
 
     evm_client.send_transaction(tx, None).await
 }
+
+/// Asks Apriori to redeem aprMON shares. The MON can be claimed with [`claim`] once the
+/// request is processed.
+pub async fn request_withdraw<P, N>(evm_client: &EvmClient<P, N>, shares: U256) -> Result<TxOutcome>
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    let tx = TransactionRequest::default()
+        .with_input(
+            IApriori::requestRedeemCall {
+                shares,
+                controller: evm_client.signer.address(),
+                owner: evm_client.signer.address(),
+            }
+            .abi_encode(),
+        )
+        .with_to(Token::APRMON.address());
+
+    evm_client.send_transaction(tx, None).await
+}
+
+/// The ids of the redeem requests sent in the transactions `hashes` that can be claimed now.
+/// Requests that are still processed or already claimed are left out.
+pub async fn claimable_requests<P, N>(
+    evm_client: &EvmClient<P, N>,
+    hashes: &[TxHash],
+) -> Result<Vec<U256>>
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    let aprmon = IApriori::new(Token::APRMON.address(), &evm_client.provider);
+    let mut request_ids = vec![];
+
+    for hash in hashes {
+        let Some(receipt) = evm_client.get_receipt(*hash).await? else { continue };
+
+        for log in receipt.inner.logs() {
+            let Ok(request) = log.log_decode::<IApriori::RedeemRequest>() else { continue };
+
+            if log.address() != Token::APRMON.address() ||
+                request.inner.controller != evm_client.address()
+            {
+                continue;
+            }
+
+            let claimable = aprmon
+                .claimableRedeemRequest(request.inner.requestId, evm_client.address())
+                .call()
+                .await
+                .map_err(ClientError::Contract)?
+                ._0;
+
+            if !claimable.is_zero() {
+                request_ids.push(request.inner.requestId);
+            }
+        }
+    }
+
+    Ok(request_ids)
+}
+
+/// Claims the MON of processed redeem requests.
+pub async fn claim<P, N>(evm_client: &EvmClient<P, N>, request_ids: Vec<U256>) -> Result<TxOutcome>
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    let tx = TransactionRequest::default()
+        .with_input(
+            IApriori::redeemCall { requestIds: request_ids, receiver: evm_client.signer.address() }
+                .abi_encode(),
+        )
+        .with_to(Token::APRMON.address());
+
+    evm_client.send_transaction(tx, None).await
+}
+
+/// Deposits a share of the MON balance into Apriori.
+pub struct AprioriDeposit;
+
+#[async_trait]
+impl<P, N> Action<P, N> for AprioriDeposit
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    fn kind(&self) -> &'static str {
+        "lending"
+    }
+
+    fn protocol(&self) -> String {
+        Lending::Apriori.to_string()
+    }
+
+    fn gas_limit(&self) -> u64 {
+        DEPOSIT_GAS_LIMIT
+    }
+
+    async fn is_available(&self, ctx: &ActionContext<'_, P, N>) -> Result<bool> {
+        Ok(!spendable_native_balance(ctx).await?.is_zero())
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ActionContext<'_, P, N>,
+        record: &mut ActionRecord,
+    ) -> Result<Option<TxOutcome>> {
+        let amount_in = pick_deposit(Lending::Apriori, ctx, record).await?;
+
+        deposit(ctx.client, amount_in).await.map(Some)
+    }
+}
+
+/// Claims the withdrawals Apriori has processed, or asks to redeem the whole aprMON balance if
+/// there are none.
+pub struct AprioriWithdraw;
+
+impl AprioriWithdraw {
+    async fn claimable_requests<P, N>(&self, ctx: &ActionContext<'_, P, N>) -> Result<Vec<U256>>
+    where
+        P: Provider<Ethereum>,
+        N: ClientNonceManager<P>,
+    {
+        let hashes = ctx.past_hashes("withdraw", &Lending::Apriori.to_string());
+        claimable_requests(ctx.client, &hashes).await
+    }
+}
+
+#[async_trait]
+impl<P, N> Action<P, N> for AprioriWithdraw
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    fn kind(&self) -> &'static str {
+        "withdraw"
+    }
+
+    fn protocol(&self) -> String {
+        Lending::Apriori.to_string()
+    }
+
+    fn gas_limit(&self) -> u64 {
+        WITHDRAW_GAS_LIMIT
+    }
+
+    async fn is_available(&self, ctx: &ActionContext<'_, P, N>) -> Result<bool> {
+        Ok(!ctx.client.get_token_balance(Token::APRMON.address()).await?.is_zero() ||
+            !self.claimable_requests(ctx).await?.is_empty())
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ActionContext<'_, P, N>,
+        record: &mut ActionRecord,
+    ) -> Result<Option<TxOutcome>> {
+        let request_ids = self.claimable_requests(ctx).await?;
+
+        if request_ids.is_empty() {
+            let shares = pick_withdrawal(Lending::Apriori, ctx, record, Token::APRMON).await?;
+            return request_withdraw(ctx.client, shares).await.map(Some);
+        }
+
+        record.token_in = Some(Token::APRMON);
+        record.token_out = Some(Token::MON);
+
+        tracing::info!("{} | Claiming {} withdrawals", Lending::Apriori, request_ids.len());
+
+        claim(ctx.client, request_ids).await.map(Some)
+    }
+}
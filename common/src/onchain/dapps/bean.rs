@@ -1,6 +1,12 @@
 use crate::{
     Result,
-    onchain::{client::Client, token::Token},
+    onchain::{
+        action::{Action, ActionContext, ActionRecord, has_swappable_balance, pick_swap},
+        client::{Client, ClientNonceManager},
+        outcome::TxOutcome,
+        token::Token,
+    },
+    state::Dex,
 };
 use alloy::{
     network::{Ethereum, TransactionBuilder},
@@ -10,10 +16,12 @@ use alloy::{
     sol,
     sol_types::SolCall,
 };
+use async_trait::async_trait;
 use chrono::{Duration, Utc};
 use thiserror::Error;
 
-use super::common::{ONE_HUNDRED, SLIPPAGE};
+use super::common::{SWAP_GAS_LIMIT, check_price_impact, min_amount_out, probe_amount};
+use crate::config::Slippage;
 
 sol! {
     #[sol(rpc)]
@@ -51,16 +59,17 @@ pub enum BeanError {
     AmountOutMissing,
 }
 
-const BEAN_ROUTER: Address = address!("0xCa810D095e90Daae6e867c19DF6D9A8C56db2c89");
+pub const BEAN_ROUTER: Address = address!("0xCa810D095e90Daae6e867c19DF6D9A8C56db2c89");
 
-async fn get_amount_out<P>(
-    client: &Client<P>,
+async fn get_amount_out<P, N>(
+    client: &Client<P, N>,
     amount_in: U256,
     token_in: &Token,
     token_out: &Token,
 ) -> Result<U256>
 where
     P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
 {
     let router = IBeanRouter::new(BEAN_ROUTER, &client.provider);
 
@@ -74,7 +83,26 @@ where
         .nth(1)
         .ok_or(BeanError::AmountOutMissing)?;
 
-    Ok(amount_out * (ONE_HUNDRED - SLIPPAGE) / ONE_HUNDRED)
+    Ok(amount_out)
+}
+
+/// The amount out Bean quotes for swapping `amount_in`, MON being traded as WMON.
+pub async fn quote<P, N>(
+    client: &Client<P, N>,
+    amount_in: U256,
+    token_in: Token,
+    token_out: Token,
+) -> Result<U256>
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    let wrapped = |token| match token {
+        Token::MON => Token::WMON,
+        token => token,
+    };
+
+    get_amount_out(client, amount_in, &wrapped(token_in), &wrapped(token_out)).await
 }
 
 fn get_deadline() -> U256 {
@@ -83,14 +111,15 @@ fn get_deadline() -> U256 {
 }
 
 // token_in will always be Wrapped MON
-async fn swap_exact_eth_for_tokens<P>(
-    client: &Client<P>,
+async fn swap_exact_eth_for_tokens<P, N>(
+    client: &Client<P, N>,
     amount_in: U256,
     amount_out: U256,
     token_out: Token,
-) -> Result<bool>
+) -> Result<TxOutcome>
 where
     P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
 {
     let tx = TransactionRequest::default()
         .with_input(
@@ -109,14 +138,15 @@ where
 }
 
 // token_out will always be Wrapped MON
-async fn swap_exact_tokens_for_eth<P>(
-    client: &Client<P>,
+async fn swap_exact_tokens_for_eth<P, N>(
+    client: &Client<P, N>,
     amount_in: U256,
     amount_out: U256,
     token_in: Token,
-) -> Result<bool>
+) -> Result<TxOutcome>
 where
     P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
 {
     let tx = TransactionRequest::default()
         .with_input(
@@ -134,15 +164,16 @@ where
     client.send_transaction(tx, None).await
 }
 
-async fn swap_exact_tokens_for_tokens<P>(
-    client: &Client<P>,
+async fn swap_exact_tokens_for_tokens<P, N>(
+    client: &Client<P, N>,
     amount_in: U256,
     amount_out: U256,
     token_in: Token,
     token_out: Token,
-) -> Result<bool>
+) -> Result<TxOutcome>
 where
     P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
 {
     let tx = TransactionRequest::default()
         .with_input(
@@ -160,19 +191,23 @@ where
     client.send_transaction(tx, None).await
 }
 
-pub async fn swap<P>(
-    client: &Client<P>,
+/// Swaps `amount_in` of `token_in` to `token_out`, approving the router first if needed. The
+/// approval is added to `record`, and `None` is returned if the swap can't go ahead after it.
+pub async fn swap<P, N>(
+    client: &Client<P, N>,
     amount_in: U256,
     token_in: Token,
     token_out: Token,
-) -> Result<bool>
+    slippage: &Slippage,
+    record: &mut ActionRecord,
+) -> Result<Option<TxOutcome>>
 where
     P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
 {
-    let approved = client.approve(token_in, BEAN_ROUTER, amount_in, false).await?;
-
-    if !approved {
-        return Ok(false);
+    let approval = client.approve(token_in, BEAN_ROUTER, amount_in, false).await?;
+    if !record.approved(Dex::Bean, Some(token_in), amount_in, approval) {
+        return Ok(None);
     }
 
     let (src, dst, case) = match (token_in, token_out) {
@@ -183,9 +218,57 @@ where
 
     let amount_out = get_amount_out(client, amount_in, &src, &dst).await?;
 
-    match case {
-        1 => swap_exact_tokens_for_eth(client, amount_in, amount_out, token_in).await,
-        2 => swap_exact_eth_for_tokens(client, amount_in, amount_out, token_out).await,
-        _ => swap_exact_tokens_for_tokens(client, amount_in, amount_out, token_in, token_out).await,
+    if let Some(probe) = probe_amount(amount_in) {
+        let probe_amount_out = get_amount_out(client, probe, &src, &dst).await?;
+        check_price_impact(amount_out, probe_amount_out, slippage)?;
+    }
+
+    let amount_out = min_amount_out(amount_out, slippage)?;
+
+    let outcome = match case {
+        1 => swap_exact_tokens_for_eth(client, amount_in, amount_out, token_in).await?,
+        2 => swap_exact_eth_for_tokens(client, amount_in, amount_out, token_out).await?,
+        _ => {
+            swap_exact_tokens_for_tokens(client, amount_in, amount_out, token_in, token_out).await?
+        }
+    };
+
+    Ok(Some(outcome))
+}
+
+/// Swaps a share of a random token the wallet holds on Bean.
+pub struct BeanSwap;
+
+#[async_trait]
+impl<P, N> Action<P, N> for BeanSwap
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    fn kind(&self) -> &'static str {
+        "swap"
+    }
+
+    fn protocol(&self) -> String {
+        Dex::Bean.to_string()
+    }
+
+    fn gas_limit(&self) -> u64 {
+        SWAP_GAS_LIMIT
+    }
+
+    async fn is_available(&self, ctx: &ActionContext<'_, P, N>) -> Result<bool> {
+        has_swappable_balance(Dex::Bean, ctx).await
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ActionContext<'_, P, N>,
+        record: &mut ActionRecord,
+    ) -> Result<Option<TxOutcome>> {
+        let (token_in, token_out, amount_in) = pick_swap(Dex::Bean, ctx, record).await?;
+        let slippage = ctx.config.slippage(Dex::Bean);
+
+        swap(ctx.client, amount_in, token_in, token_out, &slippage, record).await
     }
 }
@@ -1,4 +1,92 @@
 use alloy::primitives::U256;
+use thiserror::Error;
+
+use crate::{Result, config::Slippage};
 
-pub const SLIPPAGE: U256 = U256::from_limbs([1, 0, 0, 0]);
 pub const ONE_HUNDRED: U256 = U256::from_limbs([100, 0, 0, 0]);
+pub const BPS_DENOMINATOR: U256 = U256::from_limbs([10_000, 0, 0, 0]);
+
+/// The most gas a swap uses, the approval of the token in or the unwrap of WMON included.
+pub const SWAP_GAS_LIMIT: u64 = 400_000;
+
+/// The most gas a deposit into a lending protocol uses.
+pub const DEPOSIT_GAS_LIMIT: u64 = 250_000;
+
+/// The most gas a withdrawal from a lending protocol uses, the approval of the receipt token
+/// included.
+pub const WITHDRAW_GAS_LIMIT: u64 = 350_000;
+
+/// The reference price of a swap is quoted for this fraction of the amount in.
+pub const PRICE_PROBE_DIVISOR: U256 = U256::from_limbs([1_000, 0, 0, 0]);
+
+#[derive(Error, Debug)]
+pub enum SwapError {
+    #[error("price impact of {impact_bps} bps is above the maximum of {max_bps} bps")]
+    PriceImpactTooHigh { impact_bps: U256, max_bps: u32 },
+
+    #[error("slippage of {0} bps is above 10000 bps")]
+    SlippageOutOfRange(u32),
+
+    #[error("no quote offered for the swap")]
+    NoQuote,
+}
+
+/// Returns the amount used to quote the reference price, or None if it would round to zero.
+pub fn probe_amount(amount_in: U256) -> Option<U256> {
+    let probe = amount_in / PRICE_PROBE_DIVISOR;
+    (!probe.is_zero()).then_some(probe)
+}
+
+/// The lowest amount out that is accepted for a quote.
+///
+/// # Errors
+///
+/// Returns [`SwapError::SlippageOutOfRange`] if the slippage is above 100%.
+pub fn min_amount_out(amount_out: U256, slippage: &Slippage) -> Result<U256> {
+    let kept = BPS_DENOMINATOR
+        .checked_sub(U256::from(slippage.slippage_bps))
+        .ok_or(SwapError::SlippageOutOfRange(slippage.slippage_bps))?;
+
+    Ok(amount_out * kept / BPS_DENOMINATOR)
+}
+
+/// Compares a quote to the quote for the probe amount scaled back up.
+///
+/// # Errors
+///
+/// Returns [`SwapError::PriceImpactTooHigh`] if the quote is worse than the reference by more
+/// than the configured maximum.
+pub fn check_price_impact(
+    amount_out: U256,
+    probe_amount_out: U256,
+    slippage: &Slippage,
+) -> Result<()> {
+    check_against_reference(amount_out, probe_amount_out * PRICE_PROBE_DIVISOR, slippage)
+}
+
+/// Compares a quote to a reference quote for the same amount.
+///
+/// # Errors
+///
+/// Returns [`SwapError::PriceImpactTooHigh`] if the quote is worse than the reference by more
+/// than the configured maximum.
+pub fn check_against_reference(
+    amount_out: U256,
+    reference: U256,
+    slippage: &Slippage,
+) -> Result<()> {
+    if reference.is_zero() || amount_out >= reference {
+        return Ok(());
+    }
+
+    let impact_bps = (reference - amount_out) * BPS_DENOMINATOR / reference;
+
+    match impact_bps > U256::from(slippage.max_price_impact_bps) {
+        true => Err(SwapError::PriceImpactTooHigh {
+            impact_bps,
+            max_bps: slippage.max_price_impact_bps,
+        }
+        .into()),
+        false => Ok(()),
+    }
+}
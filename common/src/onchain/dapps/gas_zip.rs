@@ -1,14 +1,27 @@
 use crate::{
     Result,
-    onchain::{client::Client as EvmClient, error::ClientError},
+    config::Config,
+    onchain::{
+        action::{Action, ActionContext, ActionError, ActionRecord},
+        client::{Client as EvmClient, ClientNonceManager},
+        error::ClientError,
+        outcome::TxOutcome,
+        token::Token,
+    },
+    utils::random::random_in_range,
 };
 use alloy::{
     hex::FromHexError,
     network::{Ethereum, TransactionBuilder},
-    primitives::{Address, U256, address},
+    primitives::{
+        Address, U256, address,
+        utils::{format_units, parse_ether},
+    },
     providers::Provider,
     rpc::types::TransactionRequest,
 };
+use alloy_chains::NamedChain;
+use async_trait::async_trait;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -17,12 +30,14 @@ pub enum GaszipError {
     HexDecodeError(#[from] FromHexError),
 }
 
-const GAS_ZIP_CA: Address = address!("0x391E7C679d29bD940d63be94AD22A25d25b5A604");
+pub const GAS_ZIP_CA: Address = address!("0x391E7C679d29bD940d63be94AD22A25d25b5A604");
 const CALL_DATA: &str = "0x0101b1";
+const BRIDGE_GAS_LIMIT: u64 = 100_000;
 
-pub async fn bridge<P>(evm_client: &EvmClient<P>, amount_in: U256) -> Result<bool>
+pub async fn bridge<P, N>(evm_client: &EvmClient<P, N>, amount_in: U256) -> Result<TxOutcome>
 where
     P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
 {
     let call_data = alloy::hex::decode(CALL_DATA).map_err(ClientError::FromHex)?;
 
@@ -31,3 +46,62 @@ where
 
     evm_client.send_transaction(tx, None).await
 }
+
+/// Bridges a random amount of ETH from Base as set by `bridge_amount_range`. Nothing is sent if
+/// the balance doesn't cover it along with the reserve.
+pub struct GasZipBridge;
+
+#[async_trait]
+impl<P, N> Action<P, N> for GasZipBridge
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    fn kind(&self) -> &'static str {
+        "bridge"
+    }
+
+    fn protocol(&self) -> String {
+        "GASZIP".to_string()
+    }
+
+    fn chain(&self) -> NamedChain {
+        NamedChain::Base
+    }
+
+    fn gas_limit(&self) -> u64 {
+        BRIDGE_GAS_LIMIT
+    }
+
+    fn required_value(&self, config: &Config) -> U256 {
+        parse_ether(&config.bridge_amount_range[0].to_string())
+            .expect("Check your \"bridge_amount_range\" in config.toml")
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ActionContext<'_, P, N>,
+        record: &mut ActionRecord,
+    ) -> Result<Option<TxOutcome>> {
+        let native_balance = ctx.client.get_native_balance().await?;
+        let bridge_amount =
+            parse_ether(&random_in_range(ctx.config.bridge_amount_range).to_string())
+                .expect("Check your \"bridge_amount_range\" in config.toml");
+
+        if native_balance < bridge_amount + ctx.reserve {
+            return Ok(None);
+        }
+
+        record.token_in = Some(Token::ETH);
+        record.token_out = Some(Token::MON);
+        record.amount_in = Some(bridge_amount);
+
+        tracing::info!(
+            "Gaszip | bridging {} {}",
+            format_units(bridge_amount, Token::ETH.decimals()).map_err(ActionError::FormatUnits)?,
+            Token::ETH
+        );
+
+        bridge(ctx.client, bridge_amount).await.map(Some)
+    }
+}
@@ -8,12 +8,24 @@ use alloy::{
 use reqwest::{Client as ReqwestClient, header};
 use serde::{Deserialize, Serialize};
 
+use super::{
+    bean,
+    common::{BPS_DENOMINATOR, SWAP_GAS_LIMIT, SwapError, check_against_reference},
+};
 use crate::{
     Result,
+    config::Slippage,
     onchain::{
-        client::Client as EvmClient, constants::MONAD_CHAIN_ID, error::ClientError, token::Token,
+        action::{Action, ActionContext, ActionRecord, has_swappable_balance, pick_swap},
+        client::{Client as EvmClient, ClientNonceManager},
+        constants::MONAD_CHAIN_ID,
+        error::ClientError,
+        outcome::TxOutcome,
+        token::Token,
     },
+    state::Dex,
 };
+use async_trait::async_trait;
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -118,11 +130,12 @@ sol! {
     }
 }
 
-const HASHFLOW_CA: Address = address!("0xca310b1b942a30ff4b40a5e1b69ab4607ec79bc1");
-const HASHFLOW_API_URL: &str = "https://api.hashflow.com/client/v3/rfq";
+pub const HASHFLOW_CA: Address = address!("0xca310b1b942a30ff4b40a5e1b69ab4607ec79bc1");
+pub const HASHFLOW_API_URL: &str = "https://api.hashflow.com";
 
 async fn get_quote(
     http_client: ReqwestClient,
+    api_url: &str,
     token_in: Address,
     token_out: Address,
     amount_in: U256,
@@ -146,7 +159,7 @@ async fn get_quote(
     };
 
     let res = http_client
-        .post(HASHFLOW_API_URL)
+        .post(format!("{api_url}/client/v3/rfq"))
         .headers(headers)
         .json(&req)
         .send()
@@ -154,7 +167,15 @@ async fn get_quote(
         .json::<Response>()
         .await?;
 
-    Ok(res.quotes.into_iter().next().unwrap())
+    Ok(res.quotes.into_iter().next().ok_or(SwapError::NoQuote)?)
+}
+
+impl Quote {
+    fn amount_out(&self) -> Result<U256> {
+        let amount = U256::from_str_radix(&self.quote_data.quote_token_amount, 10)
+            .map_err(ClientError::Parse)?;
+        Ok(amount)
+    }
 }
 
 impl RFQTQuote {
@@ -174,8 +195,7 @@ impl RFQTQuote {
             quoteToken: token_out.address(),
             effectiveBaseTokenAmount: amount,
             baseTokenAmount: amount,
-            quoteTokenAmount: U256::from_str_radix(&quote.quote_data.quote_token_amount, 10)
-                .map_err(ClientError::Parse)?,
+            quoteTokenAmount: quote.amount_out()?,
             quoteExpiry: U256::from(quote.quote_data.quote_expiry),
             nonce: U256::from(quote.quote_data.nonce),
             txid: FixedBytes::from_hex(quote.quote_data.txid).map_err(ClientError::FromHex)?,
@@ -184,30 +204,43 @@ impl RFQTQuote {
     }
 }
 
-pub async fn swap<P>(
-    evm_client: &EvmClient<P>,
+/// Trades an RFQ quote. The quote is firm, so there is no slippage, and its price impact is
+/// measured against the Bean quote for the same amount.
+///
+/// The router is approved first if needed. The approval is added to `record`, and `None` is
+/// returned if the trade can't go ahead after it.
+#[allow(clippy::too_many_arguments)]
+pub async fn swap<P, N>(
+    evm_client: &EvmClient<P, N>,
     http_client: ReqwestClient,
+    api_url: &str,
     token_in: Token,
     token_out: Token,
     amount_in: U256,
-) -> Result<bool>
+    slippage: &Slippage,
+    record: &mut ActionRecord,
+) -> Result<Option<TxOutcome>>
 where
     P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
 {
-    let approved = evm_client.approve(token_in, HASHFLOW_CA, amount_in, false).await?;
-
-    if !approved {
-        return Ok(false);
+    let approval = evm_client.approve(token_in, HASHFLOW_CA, amount_in, false).await?;
+    if !record.approved(Dex::Hashflow, Some(token_in), amount_in, approval) {
+        return Ok(None);
     }
 
-    let quote = get_quote(
-        http_client,
-        token_in.address(),
-        token_out.address(),
-        amount_in,
-        evm_client.signer.address(),
-    )
-    .await?;
+    let trader = evm_client.signer.address();
+
+    let quote =
+        get_quote(http_client, api_url, token_in.address(), token_out.address(), amount_in, trader)
+            .await?;
+
+    // market makers turn down or pad quotes for dust, so a second RFQ for a fraction of the
+    // amount can't serve as the reference like the pool quotes of the other dexes do
+    if U256::from(slippage.max_price_impact_bps) < BPS_DENOMINATOR {
+        let reference = bean::quote(evm_client, amount_in, token_in, token_out).await?;
+        check_against_reference(quote.amount_out()?, reference, slippage)?;
+    }
 
     let contract = IHashflowRouter::new(HASHFLOW_CA, &evm_client.provider);
 
@@ -222,5 +255,52 @@ where
         .value(amount_in)
         .into_transaction_request();
 
-    evm_client.send_transaction(tx_req, None).await
+    evm_client.send_transaction(tx_req, None).await.map(Some)
+}
+
+/// Swaps a share of a random token the wallet holds through a Hashflow RFQ quote.
+pub struct HashflowSwap;
+
+#[async_trait]
+impl<P, N> Action<P, N> for HashflowSwap
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    fn kind(&self) -> &'static str {
+        "swap"
+    }
+
+    fn protocol(&self) -> String {
+        Dex::Hashflow.to_string()
+    }
+
+    fn gas_limit(&self) -> u64 {
+        SWAP_GAS_LIMIT
+    }
+
+    async fn is_available(&self, ctx: &ActionContext<'_, P, N>) -> Result<bool> {
+        has_swappable_balance(Dex::Hashflow, ctx).await
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ActionContext<'_, P, N>,
+        record: &mut ActionRecord,
+    ) -> Result<Option<TxOutcome>> {
+        let (token_in, token_out, amount_in) = pick_swap(Dex::Hashflow, ctx, record).await?;
+        let slippage = ctx.config.slippage(Dex::Hashflow);
+
+        swap(
+            ctx.client,
+            ctx.http_client.clone(),
+            HASHFLOW_API_URL,
+            token_in,
+            token_out,
+            amount_in,
+            &slippage,
+            record,
+        )
+        .await
+    }
 }
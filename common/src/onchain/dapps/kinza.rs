@@ -1,27 +1,45 @@
 use crate::{
     Result,
-    onchain::{client::Client as EvmClient, token::Token},
+    onchain::{
+        action::{
+            Action, ActionContext, ActionError, ActionRecord, pick_deposit,
+            spendable_native_balance,
+        },
+        client::{Client as EvmClient, ClientNonceManager},
+        dapps::common::{DEPOSIT_GAS_LIMIT, WITHDRAW_GAS_LIMIT},
+        outcome::TxOutcome,
+        token::Token,
+    },
+    state::Lending,
 };
 use alloy::{
     network::{Ethereum, TransactionBuilder},
-    primitives::{Address, U256, address},
+    primitives::{Address, TxHash, U256, address, utils::format_units},
     providers::Provider,
     rpc::types::TransactionRequest,
     sol,
     sol_types::SolCall,
 };
+use async_trait::async_trait;
 
 sol! {
     interface IKinza {
         function depositETH(address token, address receiver, uint16 referralCode) external payable returns (uint256);
+
+        function withdrawETH(address token, uint256 amount, address to) external;
+    }
+
+    interface IKToken {
+        event Transfer(address indexed from, address indexed to, uint256 value);
     }
 }
 
-const KINZA_CA: Address = address!("0x21d6192677f4bbff6BCCF11FC7D5c3076bFF6F1B");
+pub const KINZA_CA: Address = address!("0x21d6192677f4bbff6BCCF11FC7D5c3076bFF6F1B");
 
-pub async fn deposit<P>(evm_client: &EvmClient<P>, amount_in: U256) -> Result<bool>
+pub async fn deposit<P, N>(evm_client: &EvmClient<P, N>, amount_in: U256) -> Result<TxOutcome>
 where
     P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
 {
     let tx = TransactionRequest::default()
         .with_input(
@@ -37,3 +55,164 @@ where
 
     evm_client.send_transaction(tx, None).await
 }
+
+/// Finds the kToken minted by the deposits in the transactions `hashes`, the receipt token of
+/// the MON position, starting from the latest one.
+pub async fn position_token<P, N>(
+    evm_client: &EvmClient<P, N>,
+    hashes: &[TxHash],
+) -> Result<Option<Address>>
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    for hash in hashes.iter().rev() {
+        let Some(receipt) = evm_client.get_receipt(*hash).await? else { continue };
+
+        let token = receipt.inner.logs().iter().find_map(|log| {
+            let transfer = log.log_decode::<IKToken::Transfer>().ok()?;
+            let minted = transfer.inner.from == Address::ZERO &&
+                transfer.inner.to == evm_client.address() &&
+                log.address() != Token::WMON.address();
+
+            minted.then(|| log.address())
+        });
+
+        if token.is_some() {
+            return Ok(token);
+        }
+    }
+
+    Ok(None)
+}
+
+/// Withdraws `amount` of the MON position, approving the gateway to burn the kToken first. The
+/// approval is added to `record`, and `None` is returned if the withdrawal can't go ahead after
+/// it.
+pub async fn withdraw<P, N>(
+    evm_client: &EvmClient<P, N>,
+    ktoken: Address,
+    amount: U256,
+    record: &mut ActionRecord,
+) -> Result<Option<TxOutcome>>
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    // the kToken isn't a registered token, so the approval goes without one
+    let approval = evm_client.approve_address(ktoken, KINZA_CA, amount, false).await?;
+    if !record.approved(Lending::Kinza, None, amount, approval) {
+        return Ok(None);
+    }
+
+    let tx = TransactionRequest::default()
+        .with_input(
+            IKinza::withdrawETHCall {
+                token: Token::WMON.address(),
+                amount,
+                to: evm_client.signer.address(),
+            }
+            .abi_encode(),
+        )
+        .with_to(KINZA_CA);
+
+    evm_client.send_transaction(tx, None).await.map(Some)
+}
+
+/// Deposits a share of the MON balance into Kinza.
+pub struct KinzaDeposit;
+
+#[async_trait]
+impl<P, N> Action<P, N> for KinzaDeposit
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    fn kind(&self) -> &'static str {
+        "lending"
+    }
+
+    fn protocol(&self) -> String {
+        Lending::Kinza.to_string()
+    }
+
+    fn gas_limit(&self) -> u64 {
+        DEPOSIT_GAS_LIMIT
+    }
+
+    async fn is_available(&self, ctx: &ActionContext<'_, P, N>) -> Result<bool> {
+        Ok(!spendable_native_balance(ctx).await?.is_zero())
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ActionContext<'_, P, N>,
+        record: &mut ActionRecord,
+    ) -> Result<Option<TxOutcome>> {
+        let amount_in = pick_deposit(Lending::Kinza, ctx, record).await?;
+
+        deposit(ctx.client, amount_in).await.map(Some)
+    }
+}
+
+/// Withdraws the whole MON position from Kinza. The kToken of the position is found in the
+/// receipts of the earlier deposits.
+pub struct KinzaWithdraw;
+
+impl KinzaWithdraw {
+    /// The kToken of the position along with its balance, `None` if nothing was deposited.
+    async fn position<P, N>(&self, ctx: &ActionContext<'_, P, N>) -> Result<Option<(Address, U256)>>
+    where
+        P: Provider<Ethereum>,
+        N: ClientNonceManager<P>,
+    {
+        let hashes = ctx.past_hashes("lending", &Lending::Kinza.to_string());
+
+        match position_token(ctx.client, &hashes).await? {
+            Some(ktoken) => Ok(Some((ktoken, ctx.client.get_token_balance(ktoken).await?))),
+            None => Ok(None),
+        }
+    }
+}
+
+#[async_trait]
+impl<P, N> Action<P, N> for KinzaWithdraw
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    fn kind(&self) -> &'static str {
+        "withdraw"
+    }
+
+    fn protocol(&self) -> String {
+        Lending::Kinza.to_string()
+    }
+
+    fn gas_limit(&self) -> u64 {
+        WITHDRAW_GAS_LIMIT
+    }
+
+    async fn is_available(&self, ctx: &ActionContext<'_, P, N>) -> Result<bool> {
+        Ok(self.position(ctx).await?.is_some_and(|(_, balance)| !balance.is_zero()))
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ActionContext<'_, P, N>,
+        record: &mut ActionRecord,
+    ) -> Result<Option<TxOutcome>> {
+        let Some((ktoken, amount)) = self.position(ctx).await? else { return Ok(None) };
+
+        record.token_out = Some(Token::MON);
+        record.amount_in = Some(amount);
+
+        tracing::info!(
+            "{} | Withdrawing {} MON",
+            Lending::Kinza,
+            format_units(amount, Token::MON.decimals()).map_err(ActionError::FormatUnits)?
+        );
+
+        withdraw(ctx.client, ktoken, amount, record).await
+    }
+}
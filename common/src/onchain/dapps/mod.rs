@@ -8,3 +8,4 @@ pub mod kinza;
 pub mod mon_domains;
 pub mod nad_domains;
 pub mod shmonad;
+pub mod wmon;
@@ -1,6 +1,13 @@
 use crate::{
     Result,
-    onchain::{constants::MONAD_CHAIN_ID, error::ClientError},
+    config::Config,
+    onchain::{
+        action::{Action, ActionContext, ActionRecord},
+        constants::MONAD_CHAIN_ID,
+        error::ClientError,
+        outcome::TxOutcome,
+    },
+    state::Nft,
 };
 use alloy::{
     network::{Ethereum, TransactionBuilder},
@@ -14,7 +21,8 @@ use fake::{Fake, faker::internet::en::Username};
 use reqwest::Client as ReqwestClient;
 use serde::Deserialize;
 
-use crate::onchain::client::Client as EvmClient;
+use crate::onchain::client::{Client as EvmClient, ClientNonceManager};
+use async_trait::async_trait;
 
 #[derive(Debug, Deserialize)]
 struct SignatureResponse {
@@ -43,22 +51,32 @@ sol! {
     }
 }
 
-const NAD_DOMAINS_CA: Address = address!("0x758D80767a751fc1634f579D76e1CcaAb3485c9c");
+pub const NAD_DOMAINS_CA: Address = address!("0x758D80767a751fc1634f579D76e1CcaAb3485c9c");
+pub const NAD_DOMAINS_API_URL: &str = "https://api.nad.domains";
+/// The 0.02 MON a domain costs.
+pub const MINT_PRICE: U256 = U256::from_limbs([20_000_000_000_000_000, 0, 0, 0]);
+const MINT_GAS_LIMIT: u64 = 500_000;
 
 fn get_valid_domain_name() -> String {
     let invalid_domain_name: String = Username().fake();
     invalid_domain_name.replace("_", "")
 }
 
-pub async fn mint<P>(evm_client: &EvmClient<P>, http_client: ReqwestClient) -> Result<bool>
+pub async fn mint<P, N>(
+    evm_client: &EvmClient<P, N>,
+    http_client: ReqwestClient,
+    api_url: &str,
+) -> Result<TxOutcome>
 where
     P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
 {
     let zero_bytes = FixedBytes::<32>::ZERO;
 
     let domain_name = get_valid_domain_name();
     let url = format!(
-        "https://api.nad.domains/register/signature?name={0}&nameOwner={1}&setAsPrimaryName={2}&referrer={3}&discountKey={4}&discountClaimProof={5}&chainId={6}",
+        "{0}/register/signature?name={1}&nameOwner={2}&setAsPrimaryName={3}&referrer={4}&discountKey={5}&discountClaimProof={6}&chainId={7}",
+        api_url,
         domain_name,
         evm_client.signer.address(),
         true,
@@ -88,7 +106,43 @@ where
             .abi_encode(),
         )
         .with_to(NAD_DOMAINS_CA)
-        .with_value(U256::from(20000000000000000u64));
+        .with_value(MINT_PRICE);
 
     evm_client.send_transaction(tx, None).await
 }
+
+/// Mints a domain with a random name.
+pub struct NadDomainsMint;
+
+#[async_trait]
+impl<P, N> Action<P, N> for NadDomainsMint
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    fn kind(&self) -> &'static str {
+        "mint"
+    }
+
+    fn protocol(&self) -> String {
+        Nft::NadDomains.to_string()
+    }
+
+    fn gas_limit(&self) -> u64 {
+        MINT_GAS_LIMIT
+    }
+
+    fn required_value(&self, _config: &Config) -> U256 {
+        MINT_PRICE
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ActionContext<'_, P, N>,
+        _record: &mut ActionRecord,
+    ) -> Result<Option<TxOutcome>> {
+        tracing::info!("{} | Minting an NFT", Nft::NadDomains);
+
+        mint(ctx.client, ctx.http_client.clone(), NAD_DOMAINS_API_URL).await.map(Some)
+    }
+}
@@ -1,6 +1,16 @@
 use crate::{
     Result,
-    onchain::{client::Client as EvmClient, token::Token},
+    onchain::{
+        action::{
+            Action, ActionContext, ActionRecord, pick_deposit, pick_withdrawal,
+            spendable_native_balance,
+        },
+        client::{Client as EvmClient, ClientNonceManager},
+        dapps::common::{DEPOSIT_GAS_LIMIT, WITHDRAW_GAS_LIMIT},
+        outcome::TxOutcome,
+        token::Token,
+    },
+    state::Lending,
 };
 use alloy::{
     network::{Ethereum, TransactionBuilder},
@@ -10,16 +20,20 @@ use alloy::{
     sol,
     sol_types::SolCall,
 };
+use async_trait::async_trait;
 
 sol! {
     interface IShmonad {
         function deposit(uint256 assets, address receiver) external payable returns (uint256);
+
+        function redeem(uint256 shares, address receiver, address owner) external returns (uint256);
     }
 }
 
-pub async fn deposit<P>(evm_client: &EvmClient<P>, amount: U256) -> Result<bool>
+pub async fn deposit<P, N>(evm_client: &EvmClient<P, N>, amount: U256) -> Result<TxOutcome>
 where
     P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
 {
     let tx = TransactionRequest::default()
         .with_input(
@@ -31,3 +45,95 @@ where
 
     evm_client.send_transaction(tx, None).await
 }
+
+/// Redeems shMON shares for the MON they're worth.
+pub async fn redeem<P, N>(evm_client: &EvmClient<P, N>, shares: U256) -> Result<TxOutcome>
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    let tx = TransactionRequest::default()
+        .with_input(
+            IShmonad::redeemCall {
+                shares,
+                receiver: evm_client.signer.address(),
+                owner: evm_client.signer.address(),
+            }
+            .abi_encode(),
+        )
+        .with_to(Token::SHMON.address());
+
+    evm_client.send_transaction(tx, None).await
+}
+
+/// Deposits a share of the MON balance into shMonad.
+pub struct ShmonadDeposit;
+
+#[async_trait]
+impl<P, N> Action<P, N> for ShmonadDeposit
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    fn kind(&self) -> &'static str {
+        "lending"
+    }
+
+    fn protocol(&self) -> String {
+        Lending::Shmonad.to_string()
+    }
+
+    fn gas_limit(&self) -> u64 {
+        DEPOSIT_GAS_LIMIT
+    }
+
+    async fn is_available(&self, ctx: &ActionContext<'_, P, N>) -> Result<bool> {
+        Ok(!spendable_native_balance(ctx).await?.is_zero())
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ActionContext<'_, P, N>,
+        record: &mut ActionRecord,
+    ) -> Result<Option<TxOutcome>> {
+        let amount_in = pick_deposit(Lending::Shmonad, ctx, record).await?;
+
+        deposit(ctx.client, amount_in).await.map(Some)
+    }
+}
+
+/// Redeems the whole shMON balance.
+pub struct ShmonadRedeem;
+
+#[async_trait]
+impl<P, N> Action<P, N> for ShmonadRedeem
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    fn kind(&self) -> &'static str {
+        "withdraw"
+    }
+
+    fn protocol(&self) -> String {
+        Lending::Shmonad.to_string()
+    }
+
+    fn gas_limit(&self) -> u64 {
+        WITHDRAW_GAS_LIMIT
+    }
+
+    async fn is_available(&self, ctx: &ActionContext<'_, P, N>) -> Result<bool> {
+        Ok(!ctx.client.get_token_balance(Token::SHMON.address()).await?.is_zero())
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ActionContext<'_, P, N>,
+        record: &mut ActionRecord,
+    ) -> Result<Option<TxOutcome>> {
+        let shares = pick_withdrawal(Lending::Shmonad, ctx, record, Token::SHMON).await?;
+
+        redeem(ctx.client, shares).await.map(Some)
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{
+    Result,
+    onchain::{
+        action::{
+            Action, ActionContext, ActionRecord, pick_deposit, pick_withdrawal,
+            spendable_native_balance,
+        },
+        client::{Client as EvmClient, ClientNonceManager},
+        outcome::TxOutcome,
+        token::Token,
+    },
+};
+use alloy::{
+    network::{Ethereum, TransactionBuilder},
+    primitives::U256,
+    providers::Provider,
+    rpc::types::TransactionRequest,
+    sol,
+    sol_types::SolCall,
+};
+use async_trait::async_trait;
+
+pub const WMON_PROTOCOL: &str = "WMON";
+
+/// The most gas a wrap or an unwrap uses.
+pub const WRAP_GAS_LIMIT: u64 = 100_000;
+
+sol! {
+    interface IWMON {
+        function deposit() external payable;
+
+        function withdraw(uint256 wad) external;
+    }
+}
+
+/// Wraps MON into the same amount of WMON.
+pub async fn wrap<P, N>(evm_client: &EvmClient<P, N>, amount: U256) -> Result<TxOutcome>
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    let tx = TransactionRequest::default()
+        .with_input(IWMON::depositCall {}.abi_encode())
+        .with_to(Token::WMON.address())
+        .with_value(amount);
+
+    evm_client.send_transaction(tx, None).await
+}
+
+/// Unwraps WMON back into the same amount of MON.
+pub async fn unwrap<P, N>(evm_client: &EvmClient<P, N>, amount: U256) -> Result<TxOutcome>
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    let tx = TransactionRequest::default()
+        .with_input(IWMON::withdrawCall { wad: amount }.abi_encode())
+        .with_to(Token::WMON.address());
+
+    evm_client.send_transaction(tx, None).await
+}
+
+/// Wraps a share of the MON balance as set by `deposit_ratio`.
+pub struct WmonWrap;
+
+#[async_trait]
+impl<P, N> Action<P, N> for WmonWrap
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    fn kind(&self) -> &'static str {
+        "wrap"
+    }
+
+    fn protocol(&self) -> String {
+        WMON_PROTOCOL.to_string()
+    }
+
+    fn gas_limit(&self) -> u64 {
+        WRAP_GAS_LIMIT
+    }
+
+    async fn is_available(&self, ctx: &ActionContext<'_, P, N>) -> Result<bool> {
+        Ok(!spendable_native_balance(ctx).await?.is_zero())
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ActionContext<'_, P, N>,
+        record: &mut ActionRecord,
+    ) -> Result<Option<TxOutcome>> {
+        let amount_in = pick_deposit(WMON_PROTOCOL, ctx, record).await?;
+        record.token_out = Some(Token::WMON);
+
+        wrap(ctx.client, amount_in).await.map(Some)
+    }
+}
+
+/// Unwraps the whole WMON balance.
+pub struct WmonUnwrap;
+
+#[async_trait]
+impl<P, N> Action<P, N> for WmonUnwrap
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    fn kind(&self) -> &'static str {
+        "unwrap"
+    }
+
+    fn protocol(&self) -> String {
+        WMON_PROTOCOL.to_string()
+    }
+
+    fn gas_limit(&self) -> u64 {
+        WRAP_GAS_LIMIT
+    }
+
+    async fn is_available(&self, ctx: &ActionContext<'_, P, N>) -> Result<bool> {
+        Ok(!ctx.client.get_token_balance(Token::WMON.address()).await?.is_zero())
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ActionContext<'_, P, N>,
+        record: &mut ActionRecord,
+    ) -> Result<Option<TxOutcome>> {
+        let amount_in = pick_withdrawal(WMON_PROTOCOL, ctx, record, Token::WMON).await?;
+
+        unwrap(ctx.client, amount_in).await.map(Some)
+    }
+}
@@ -8,6 +8,8 @@ use alloy::{
 };
 use thiserror::Error;
 
+use crate::error::ErrorClass;
+
 #[derive(Debug, Error)]
 pub enum ClientError {
     #[error(transparent)]
@@ -31,9 +33,71 @@ pub enum ClientError {
     #[error("tx type `{0}` is not supported")]
     UnexpectedTxType(TxType),
 
+    #[error("network fee of {fee} wei is above the cap of {cap} wei")]
+    FeeAboveCap { fee: u128, cap: u128 },
+
+    #[error("no rpc endpoints are configured")]
+    NoRpcEndpoints,
+
+    #[error("transaction with nonce {nonce} is still pending after {attempts} attempts")]
+    StuckTx { nonce: u64, attempts: u32 },
+
     #[error(transparent)]
     Contract(#[from] alloy::contract::Error),
 
     #[error(transparent)]
     Multicall(#[from] MulticallError),
 }
+
+impl ClientError {
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            ClientError::Rpc(e) => rpc_error_class(e),
+            ClientError::PendingTx(PendingTransactionError::TransportError(e)) => {
+                rpc_error_class(e)
+            }
+            ClientError::PendingTx(_) => ErrorClass::TransientRpc,
+            ClientError::FeeAboveCap { .. } | ClientError::StuckTx { .. } => {
+                ErrorClass::TransientRpc
+            }
+            ClientError::Contract(alloy::contract::Error::TransportError(e)) => rpc_error_class(e),
+            ClientError::Contract(alloy::contract::Error::PendingTransactionError(_)) => {
+                ErrorClass::TransientRpc
+            }
+            ClientError::Multicall(MulticallError::TransportError(e)) => rpc_error_class(e),
+            ClientError::Multicall(MulticallError::CallFailed(_)) => ErrorClass::Revert,
+            ClientError::Signer(_) |
+            ClientError::Parse(_) |
+            ClientError::FromHex(_) |
+            ClientError::UnbuiltTx(_) |
+            ClientError::UnexpectedTxType(_) |
+            ClientError::NoRpcEndpoints |
+            ClientError::Contract(_) |
+            ClientError::Multicall(_) => ErrorClass::Permanent,
+        }
+    }
+}
+
+/// Tells the failures of the node itself apart from the answers it gave about the transaction.
+fn rpc_error_class(e: &RpcErr<TransportErrorKind>) -> ErrorClass {
+    match e {
+        RpcErr::Transport(kind) if kind.is_retry_err() => ErrorClass::RateLimit,
+        RpcErr::ErrorResp(payload) => {
+            let message = payload.message.to_lowercase();
+
+            if payload.is_retry_err() || message.contains("rate limit") {
+                ErrorClass::RateLimit
+            } else if message.contains("insufficient funds") ||
+                message.contains("insufficient balance")
+            {
+                ErrorClass::InsufficientBalance
+            } else if message.contains("revert") {
+                ErrorClass::Revert
+            } else {
+                ErrorClass::TransientRpc
+            }
+        }
+        RpcErr::UnsupportedFeature(_) | RpcErr::LocalUsageError(_) => ErrorClass::Permanent,
+        _ => ErrorClass::TransientRpc,
+    }
+}
@@ -1,5 +1,9 @@
+pub mod action;
 pub mod client;
 pub mod constants;
 pub mod dapps;
 pub mod error;
+pub mod outcome;
+pub mod rate_limit;
 pub mod token;
+pub mod transport;
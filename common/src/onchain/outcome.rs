@@ -0,0 +1,53 @@
+use alloy::{
+    primitives::{TxHash, U256},
+    rpc::types::TransactionReceipt,
+};
+
+/// The result of a transaction that made it into a block, or of its simulation.
+#[derive(Debug, Clone)]
+pub struct TxOutcome {
+    pub hash: TxHash,
+    pub success: bool,
+    pub gas_used: u64,
+    pub effective_gas_price: u128,
+    pub block_number: Option<u64>,
+    /// Decoded revert reason, only present for reverted transactions.
+    pub revert_reason: Option<String>,
+    /// The transaction was only simulated, the hash is zero and gas is an estimate.
+    pub simulated: bool,
+    /// Number of transactions sent with the same nonce, including replacements.
+    pub attempts: u32,
+    /// The transaction got stuck and a zero-value transfer to self was mined in its place.
+    pub cancelled: bool,
+}
+
+impl TxOutcome {
+    pub fn from_receipt(receipt: &TransactionReceipt, revert_reason: Option<String>) -> Self {
+        Self {
+            hash: receipt.transaction_hash,
+            success: receipt.status(),
+            gas_used: receipt.gas_used,
+            effective_gas_price: receipt.effective_gas_price,
+            block_number: receipt.block_number,
+            revert_reason,
+            simulated: false,
+            attempts: 1,
+            cancelled: false,
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.success && !self.cancelled
+    }
+
+    /// Whether the transaction that needs this approval can't go ahead: the approval failed, or
+    /// it was only simulated and so isn't applied for the next simulation to see.
+    pub fn blocks_next(&self) -> bool {
+        !self.is_success() || self.simulated
+    }
+
+    /// Total fee paid for the transaction in wei.
+    pub fn gas_cost(&self) -> U256 {
+        U256::from(self.gas_used) * U256::from(self.effective_gas_price)
+    }
+}
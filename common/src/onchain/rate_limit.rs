@@ -0,0 +1,32 @@
+use std::{sync::Arc, time::Duration};
+use tokio::{sync::Mutex, time::Instant};
+
+/// Spaces out the transactions broadcast on a chain so that no more than `max_tps` go out per
+/// second. Clones share the schedule, so one limiter is handed to every client of the chain.
+#[derive(Debug, Clone)]
+pub struct TxRateLimiter {
+    interval: Duration,
+    next_slot: Arc<Mutex<Instant>>,
+}
+
+impl TxRateLimiter {
+    /// Panics unless `max_tps` is positive, which the config is checked for when it's read.
+    pub fn new(max_tps: f64) -> Self {
+        Self {
+            interval: Duration::from_secs_f64(1.0 / max_tps),
+            next_slot: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// Waits for the next free slot.
+    pub async fn acquire(&self) {
+        let slot = {
+            let mut next_slot = self.next_slot.lock().await;
+            let slot = (*next_slot).max(Instant::now());
+            *next_slot = slot + self.interval;
+            slot
+        };
+
+        tokio::time::sleep_until(slot).await;
+    }
+}
@@ -1,79 +1,471 @@
-use std::fmt::Display;
+use std::{collections::HashSet, fmt::Display, path::Path, sync::OnceLock};
 
-use alloy::primitives::{Address, address};
+use alloy::{
+    network::Ethereum,
+    primitives::{Address, U256},
+    providers::{MulticallBuilder, Provider},
+    sol,
+};
+use alloy_chains::NamedChain;
 use rand::seq::IteratorRandom;
-use strum::{EnumIter, IntoEnumIterator};
-
-#[allow(clippy::upper_case_acronyms)]
-#[derive(Clone, Copy, Debug, PartialEq, EnumIter, Eq, PartialOrd, Ord, Hash)]
-pub enum Token {
-    MON,
-    USDC,
-    WMON,
-    SHMON,
-    APRMON,
-    ETH,
+use serde::Deserialize;
+use strum::IntoEnumIterator;
+use thiserror::Error;
+
+use crate::{
+    Result,
+    onchain::{dapps::ambient::Pool, error::ClientError},
+    state::Dex,
+};
+
+static REGISTRY: OnceLock<TokenRegistry> = OnceLock::new();
+
+sol! {
+    #[sol(rpc)]
+    interface IERC20Metadata {
+        function balanceOf(address account) external view returns (uint256);
+
+        function decimals() external view returns (uint8);
+
+        function symbol() external view returns (string);
+    }
 }
 
-impl Token {
-    pub const fn decimals(&self) -> u8 {
+#[derive(Error, Debug)]
+pub enum TokenError {
+    #[error("token `{0}` is listed more than once")]
+    DuplicateSymbol(String),
+
+    #[error("token `{symbol}` has the same address as another token on {chain}")]
+    DuplicateAddress { symbol: String, chain: NamedChain },
+
+    #[error("{0} must have exactly one native token")]
+    NativeCount(NamedChain),
+
+    #[error("token `{0}` must have the zero address if and only if it's native")]
+    NativeAddress(String),
+
+    #[error("token `{symbol}` lists the unknown dex `{dex}`")]
+    UnknownDex { symbol: String, dex: String },
+
+    #[error("Ambient has no pool to swap `{from}` to `{to}`")]
+    NoAmbientPool { from: String, to: String },
+
+    #[error(
+        "token `{0}` is listed for HASHFLOW but not for BEAN, whose quotes Hashflow's are checked against"
+    )]
+    NoHashflowReference(String),
+
+    #[error("token `{symbol}` is required on {chain}")]
+    Missing { symbol: &'static str, chain: NamedChain },
+
+    #[error("the token registry was read already")]
+    AlreadyRead,
+
+    #[error("{count} token(s) of `data/tokens.toml` don't match their contracts on {chain}")]
+    Mismatch { chain: NamedChain, count: usize },
+}
+
+/// A difference between a token of the registry and its contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenMismatch {
+    NoCode {
+        token: Token,
+    },
+    Decimals {
+        token: Token,
+        listed: u8,
+        onchain: u8,
+    },
+    Symbol {
+        token: Token,
+        onchain: String,
+    },
+    /// `decimals()` or `symbol()` reverted or returned something else than expected.
+    Unreadable {
+        token: Token,
+    },
+}
+
+impl Display for TokenMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Token::MON => 18,
-            Token::USDC => 6,
-            Token::WMON => 18,
-            Token::SHMON => 18,
-            Token::APRMON => 18,
-            Token::ETH => 18,
+            TokenMismatch::NoCode { token } => {
+                write!(f, "{token} | There is no contract at {}", token.address())
+            }
+            TokenMismatch::Decimals { token, listed, onchain } => {
+                write!(f, "{token} | Listed with {listed} decimals, the contract has {onchain}")
+            }
+            TokenMismatch::Symbol { token, onchain } => {
+                write!(f, "{token} | The contract at {} is `{onchain}`", token.address())
+            }
+            TokenMismatch::Unreadable { token } => write!(
+                f,
+                "{token} | The contract at {} doesn't answer `decimals()` and `symbol()`",
+                token.address()
+            ),
         }
     }
+}
 
-    pub const fn address(&self) -> Address {
-        match self {
-            Token::MON => Address::ZERO,
-            Token::USDC => address!("0xf817257fed379853cde0fa4f97ab987181b1e5ea"),
-            Token::WMON => address!("0x760AfE86e5de5fa0Ee542fc7B7B713e1c5425701"),
-            Token::SHMON => address!("0x3a98250F98Dd388C211206983453837C8365BDc1"),
-            Token::APRMON => address!("0xb2f82D0f38dc453D596Ad40A37799446Cc89274A"),
-            Token::ETH => Address::ZERO, // Using for the Base chain
+/// A token as it's listed in `data/tokens.toml`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TokenInfo {
+    pub symbol: String,
+    pub address: Address,
+    pub decimals: u8,
+    #[serde(default)]
+    pub native: bool,
+    /// Whether swaps may pick the token.
+    #[serde(default)]
+    pub swap: bool,
+    /// The dexes that can trade the token, as they're named in the goals, e.g. `AMBIENT`.
+    #[serde(default)]
+    pub dexes: Vec<String>,
+}
+
+/// The tokens of every chain, read from `data/tokens.toml`.
+#[derive(Debug, Deserialize)]
+pub struct TokenRegistry {
+    #[serde(default)]
+    monad: Vec<TokenInfo>,
+    #[serde(default)]
+    base: Vec<TokenInfo>,
+}
+
+impl TokenRegistry {
+    const PATH: &str = "data/tokens.toml";
+
+    /// The tokens the protocols are built on and the chain each one must be listed on.
+    const REQUIRED: [(Token, NamedChain); 6] = [
+        (Token::MON, NamedChain::MonadTestnet),
+        (Token::USDC, NamedChain::MonadTestnet),
+        (Token::WMON, NamedChain::MonadTestnet),
+        (Token::SHMON, NamedChain::MonadTestnet),
+        (Token::APRMON, NamedChain::MonadTestnet),
+        (Token::ETH, NamedChain::Base),
+    ];
+
+    pub fn parse(s: &str) -> Result<Self> {
+        let registry: Self = toml::from_str(s)?;
+        registry.validate()?;
+        Ok(registry)
+    }
+
+    /// Reads `data/tokens.toml` and makes it the registry every [`Token`] is looked up in.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TokenError::AlreadyRead`] if the registry was set before, it's never replaced.
+    pub async fn read_default() -> Result<()> {
+        Self::read_from_file(Self::PATH).await
+    }
+
+    async fn read_from_file(path: impl AsRef<Path>) -> Result<()> {
+        let registry = Self::parse(&tokio::fs::read_to_string(path).await?)?;
+        REGISTRY.set(registry).map_err(|_| TokenError::AlreadyRead)?;
+        Ok(())
+    }
+
+    /// The registry set by [`TokenRegistry::read_default`], if it was read.
+    #[cfg(not(feature = "shipped-tokens"))]
+    fn get() -> Option<&'static Self> {
+        REGISTRY.get()
+    }
+
+    /// The registry set by [`TokenRegistry::read_default`], or the shipped tokens if it wasn't
+    /// read, so that tests can use the tokens without the file.
+    #[cfg(feature = "shipped-tokens")]
+    fn get() -> Option<&'static Self> {
+        Some(REGISTRY.get_or_init(|| {
+            Self::parse(include_str!("../../../data/tokens.toml"))
+                .expect("shipped tokens to be valid")
+        }))
+    }
+
+    fn chains(&self) -> [(NamedChain, &[TokenInfo]); 2] {
+        [(NamedChain::MonadTestnet, &self.monad), (NamedChain::Base, &self.base)]
+    }
+
+    fn find(&self, symbol: &str) -> Option<(NamedChain, &TokenInfo)> {
+        self.chains().into_iter().find_map(|(chain, tokens)| {
+            tokens.iter().find(|token| token.symbol == symbol).map(|token| (chain, token))
+        })
+    }
+
+    fn validate(&self) -> std::result::Result<(), TokenError> {
+        let dexes = Dex::iter().map(|dex| dex.to_string()).collect::<HashSet<_>>();
+        let mut symbols = HashSet::new();
+
+        for (chain, tokens) in self.chains() {
+            let mut addresses = HashSet::new();
+
+            if tokens.iter().filter(|token| token.native).count() != 1 {
+                return Err(TokenError::NativeCount(chain));
+            }
+
+            for token in tokens {
+                // the transaction history refers to tokens by symbol only
+                if !symbols.insert(token.symbol.as_str()) {
+                    return Err(TokenError::DuplicateSymbol(token.symbol.clone()));
+                }
+
+                if token.native != token.address.is_zero() {
+                    return Err(TokenError::NativeAddress(token.symbol.clone()));
+                }
+
+                if !addresses.insert(token.address) {
+                    return Err(TokenError::DuplicateAddress {
+                        symbol: token.symbol.clone(),
+                        chain,
+                    });
+                }
+
+                if let Some(dex) = token.dexes.iter().find(|dex| !dexes.contains(*dex)) {
+                    return Err(TokenError::UnknownDex {
+                        symbol: token.symbol.clone(),
+                        dex: dex.clone(),
+                    });
+                }
+            }
+        }
+
+        // Ambient only trades through the pools it knows, and any token it supports can be swapped
+        // to any of them that allows swaps
+        let ambient = Dex::Ambient.to_string();
+        let on_ambient = self
+            .chains()
+            .into_iter()
+            .flat_map(|(_, tokens)| tokens)
+            .filter(|token| token.dexes.contains(&ambient))
+            .collect::<Vec<_>>();
+
+        for from in &on_ambient {
+            for to in on_ambient.iter().filter(|to| to.swap && to.symbol != from.symbol) {
+                if !Pool::exists(&from.symbol, &to.symbol) {
+                    return Err(TokenError::NoAmbientPool {
+                        from: from.symbol.clone(),
+                        to: to.symbol.clone(),
+                    });
+                }
+            }
+        }
+
+        // the price impact of a Hashflow quote is measured against the Bean quote
+        let (hashflow, bean) = (Dex::Hashflow.to_string(), Dex::Bean.to_string());
+        for token in self.chains().into_iter().flat_map(|(_, tokens)| tokens) {
+            if token.dexes.contains(&hashflow) && !token.dexes.contains(&bean) {
+                return Err(TokenError::NoHashflowReference(token.symbol.clone()));
+            }
+        }
+
+        for (token, chain) in Self::REQUIRED {
+            if self.find(token.0).is_none_or(|(listed_on, _)| listed_on != chain) {
+                return Err(TokenError::Missing { symbol: token.0, chain });
+            }
         }
+
+        Ok(())
     }
+}
 
-    pub const fn is_native(&self) -> bool {
-        match self {
-            Token::MON => true,
-            Token::USDC => false,
-            Token::WMON => false,
-            Token::SHMON => false,
-            Token::APRMON => false,
-            Token::ETH => true,
+/// Compares every non-native token of the chain with its contract: the contract must exist and
+/// its `decimals()` and `symbol()` must match the registry, the symbol ignoring case.
+pub async fn check_tokens<P>(provider: &P, chain: NamedChain) -> Result<Vec<TokenMismatch>>
+where
+    P: Provider<Ethereum>,
+{
+    let mut mismatches = vec![];
+    let mut deployed = vec![];
+
+    for token in Token::all().filter(|t| t.chain() == chain && !t.is_native()) {
+        let code = provider.get_code_at(token.address()).await.map_err(ClientError::Rpc)?;
+
+        match code.is_empty() {
+            true => mismatches.push(TokenMismatch::NoCode { token }),
+            false => deployed.push(token),
         }
     }
 
-    pub const fn is_swap_allowed(&self) -> bool {
-        match self {
-            Token::MON => true,
-            Token::USDC => true,
-            Token::WMON => false,
-            Token::SHMON => false,
-            Token::APRMON => false,
-            Token::ETH => false,
+    if deployed.is_empty() {
+        return Ok(mismatches);
+    }
+
+    let (decimals, symbols) = deployed.iter().fold(
+        (MulticallBuilder::new_dynamic(provider), MulticallBuilder::new_dynamic(provider)),
+        |(decimals, symbols), token| {
+            let erc20 = IERC20Metadata::new(token.address(), provider);
+            (decimals.add_dynamic(erc20.decimals()), symbols.add_dynamic(erc20.symbol()))
+        },
+    );
+
+    let decimals = decimals.try_aggregate(false).await.map_err(ClientError::Multicall)?;
+    let symbols = symbols.try_aggregate(false).await.map_err(ClientError::Multicall)?;
+
+    for ((token, decimals), symbol) in deployed.into_iter().zip(decimals).zip(symbols) {
+        let (Ok(decimals), Ok(symbol)) = (decimals, symbol) else {
+            mismatches.push(TokenMismatch::Unreadable { token });
+            continue;
+        };
+
+        if decimals._0 != token.decimals() {
+            mismatches.push(TokenMismatch::Decimals {
+                token,
+                listed: token.decimals(),
+                onchain: decimals._0,
+            });
+        }
+
+        if !symbol._0.eq_ignore_ascii_case(token.ticker()) {
+            mismatches.push(TokenMismatch::Symbol { token, onchain: symbol._0 });
         }
     }
 
+    Ok(mismatches)
+}
+
+/// Runs [`check_tokens`], logs every mismatch and fails if there's any, so that amounts are never
+/// scaled by wrong decimals.
+pub async fn verify_tokens<P>(provider: &P, chain: NamedChain) -> Result<()>
+where
+    P: Provider<Ethereum>,
+{
+    let mismatches = check_tokens(provider, chain).await?;
+
+    for mismatch in &mismatches {
+        tracing::error!("{mismatch}");
+    }
+
+    match mismatches.len() {
+        0 => Ok(()),
+        count => Err(TokenError::Mismatch { chain, count }.into()),
+    }
+}
+
+/// The balance of every token of the chain held by `owner`, zero balances included, in the order
+/// of the registry.
+pub async fn balances_of<P>(
+    provider: &P,
+    chain: NamedChain,
+    owner: Address,
+) -> Result<Vec<(Token, U256)>>
+where
+    P: Provider<Ethereum>,
+{
+    let tokens = Token::all().filter(|t| t.chain() == chain).collect::<Vec<_>>();
+    let contracts = tokens.iter().filter(|t| !t.is_native()).collect::<Vec<_>>();
+
+    let mut contract_balances = match contracts.is_empty() {
+        true => vec![],
+        false => contracts
+            .iter()
+            .fold(MulticallBuilder::new_dynamic(provider), |builder, token| {
+                let erc20 = IERC20Metadata::new(token.address(), provider);
+                builder.add_dynamic(erc20.balanceOf(owner))
+            })
+            .aggregate()
+            .await
+            .map_err(ClientError::Multicall)?
+            .into_iter()
+            .map(|ret| ret._0)
+            .collect(),
+    }
+    .into_iter();
+
+    let mut balances = vec![];
+
+    for token in tokens {
+        let balance = match token.is_native() {
+            true => provider.get_balance(owner).await.map_err(ClientError::Rpc)?,
+            false => contract_balances.next().unwrap_or_default(),
+        };
+        balances.push((token, balance));
+    }
+
+    Ok(balances)
+}
+
+/// A token of the [`TokenRegistry`], identified by its symbol.
+///
+/// The tokens the protocols are built on have a constant, any other token is found with
+/// [`Token::from_ticker`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Token(&'static str);
+
+impl Token {
+    pub const MON: Token = Token("MON");
+    pub const USDC: Token = Token("USDC");
+    pub const WMON: Token = Token("wMON");
+    pub const SHMON: Token = Token("shMON");
+    pub const APRMON: Token = Token("aprMON");
+    pub const ETH: Token = Token("ETH"); // Using for the Base chain
+
+    fn info(&self) -> &'static TokenInfo {
+        self.find().1
+    }
+
+    /// The chain and listing of the token. A token only exists for a symbol of the registry, and
+    /// the registry is never replaced, so only the constants of a registry that wasn't read
+    /// aren't found.
+    fn find(&self) -> (NamedChain, &'static TokenInfo) {
+        TokenRegistry::get()
+            .expect("token registry to be read before a token is used")
+            .find(self.0)
+            .expect("token to be registered")
+    }
+
+    pub fn decimals(&self) -> u8 {
+        self.info().decimals
+    }
+
+    pub fn address(&self) -> Address {
+        self.info().address
+    }
+
+    pub fn chain(&self) -> NamedChain {
+        self.find().0
+    }
+
+    pub fn is_native(&self) -> bool {
+        self.info().native
+    }
+
+    pub fn is_swap_allowed(&self) -> bool {
+        self.info().swap
+    }
+
+    /// Whether the dex, named as in the goals, can trade the token.
+    pub fn is_supported_by(&self, dex: &str) -> bool {
+        self.info().dexes.iter().any(|supported| supported == dex)
+    }
+
     pub fn ticker(&self) -> &'static str {
-        match self {
-            Token::MON => "MON",
-            Token::USDC => "USDC",
-            Token::WMON => "wMON",
-            Token::SHMON => "shMON",
-            Token::APRMON => "aprMON",
-            Token::ETH => "ETH",
-        }
+        self.0
+    }
+
+    pub fn from_ticker(ticker: &str) -> Option<Token> {
+        Token::all().find(|t| t.ticker() == ticker)
+    }
+
+    /// Every token of the registry, none if it wasn't read.
+    pub fn all() -> impl Iterator<Item = Token> {
+        TokenRegistry::get()
+            .into_iter()
+            .flat_map(TokenRegistry::chains)
+            .flat_map(|(_, tokens)| tokens.iter().map(|token| Token(token.symbol.as_str())))
     }
 
-    pub fn random_excluding(exclude: Token) -> Token {
+    /// A random token on the chain of `exclude`, other than `exclude`, that the dex can swap to.
+    pub fn random_swap_target(exclude: Token, dex: &str) -> Option<Token> {
         let mut rng = rand::rng();
-        Token::iter().filter(|&t| t != exclude && t.is_swap_allowed()).choose(&mut rng).unwrap() // unless there are at least 2 enum variants the unwrap is safe
+        Token::all()
+            .filter(|&t| {
+                t != exclude &&
+                    t.chain() == exclude.chain() &&
+                    t.is_swap_allowed() &&
+                    t.is_supported_by(dex)
+            })
+            .choose(&mut rng)
     }
 }
 
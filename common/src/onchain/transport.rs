@@ -0,0 +1,191 @@
+use alloy::{
+    rpc::json_rpc::{Id, Request, RequestPacket, ResponsePacket},
+    transports::{
+        TransportError, TransportErrorKind, TransportFut, TransportResult,
+        http::{Http, reqwest::Client as ReqwestClient},
+    },
+};
+use std::{
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use tower::Service;
+use url::Url;
+
+use super::error::ClientError;
+use crate::{Result, config::RpcEndpoint};
+
+/// An HTTP transport that spreads requests over several RPC endpoints by their weight.
+///
+/// An endpoint that fails with a transport error or answers with a rate limit is put on a
+/// cooldown and the request is sent to the next one. Endpoints on a cooldown are only tried
+/// once every healthy endpoint has failed.
+#[derive(Clone)]
+pub struct FailoverTransport {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    endpoints: Vec<Endpoint>,
+    cooldown: Duration,
+}
+
+struct Endpoint {
+    transport: Http<ReqwestClient>,
+    weight: u32,
+    unhealthy_until: Mutex<Option<Instant>>,
+}
+
+impl FailoverTransport {
+    pub fn new(endpoints: &[RpcEndpoint], cooldown: Duration) -> Result<Self> {
+        if endpoints.is_empty() {
+            return Err(ClientError::NoRpcEndpoints.into());
+        }
+
+        let endpoints = endpoints
+            .iter()
+            .map(|endpoint| {
+                Ok(Endpoint {
+                    transport: Http::new(Url::parse(endpoint.url())?),
+                    weight: endpoint.weight().max(1),
+                    unhealthy_until: Mutex::new(None),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { inner: Arc::new(Inner { endpoints, cooldown }) })
+    }
+
+    /// Checks every endpoint with `eth_blockNumber` each `interval` for as long as the transport
+    /// is alive, so that a failed endpoint gets back into rotation once it recovers and a broken
+    /// one is taken out before a request hits it.
+    pub fn with_health_checks(self, interval: Duration) -> Self {
+        let inner = Arc::downgrade(&self.inner);
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+
+                let Some(inner) = inner.upgrade() else { break };
+                inner.check_health().await;
+            }
+        });
+
+        self
+    }
+}
+
+impl Inner {
+    /// Healthy endpoints come first in a random order weighted by their weight, followed by the
+    /// ones on a cooldown, soonest to recover first.
+    fn order(&self) -> Vec<&Endpoint> {
+        let now = Instant::now();
+
+        let (healthy, mut unhealthy): (Vec<_>, Vec<_>) = self
+            .endpoints
+            .iter()
+            .map(|endpoint| (endpoint, endpoint.unhealthy_until()))
+            .partition(|(_, until)| until.is_none_or(|until| until <= now));
+
+        // weighted random shuffle, an endpoint with twice the weight is twice as likely to go
+        // first
+        let mut healthy = healthy
+            .into_iter()
+            .map(|(endpoint, _)| {
+                (rand::random::<f64>().powf(1.0 / endpoint.weight as f64), endpoint)
+            })
+            .collect::<Vec<_>>();
+        healthy.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+        unhealthy.sort_by_key(|(_, until)| *until);
+
+        healthy
+            .into_iter()
+            .map(|(_, endpoint)| endpoint)
+            .chain(unhealthy.into_iter().map(|(endpoint, _)| endpoint))
+            .collect()
+    }
+
+    async fn send(&self, req: RequestPacket) -> TransportResult<ResponsePacket> {
+        let mut last = Err(TransportErrorKind::backend_gone());
+
+        for endpoint in self.order() {
+            let res = endpoint.transport.clone().call(req.clone()).await;
+
+            match &res {
+                Ok(res) if !is_rate_limited(res) => {
+                    endpoint.mark_healthy();
+                    return Ok(res.clone());
+                }
+                Ok(_) => endpoint.mark_unhealthy(self.cooldown, "rate limited"),
+                Err(e) => endpoint.mark_unhealthy(self.cooldown, e),
+            }
+
+            last = res;
+        }
+
+        last
+    }
+
+    async fn check_health(&self) {
+        let req = match Request::new("eth_blockNumber", Id::Number(0), ()).serialize() {
+            Ok(req) => RequestPacket::Single(req),
+            Err(e) => {
+                tracing::error!("Failed to build the health check request: {e}");
+                return;
+            }
+        };
+
+        for endpoint in &self.endpoints {
+            match endpoint.transport.clone().call(req.clone()).await {
+                Ok(res) if !res.is_error() => endpoint.mark_healthy(),
+                Ok(_) => endpoint.mark_unhealthy(self.cooldown, "health check failed"),
+                Err(e) => endpoint.mark_unhealthy(self.cooldown, e),
+            }
+        }
+    }
+}
+
+impl Endpoint {
+    fn unhealthy_until(&self) -> Option<Instant> {
+        *self.unhealthy_until.lock().unwrap()
+    }
+
+    fn mark_healthy(&self) {
+        let mut unhealthy_until = self.unhealthy_until.lock().unwrap();
+
+        if unhealthy_until.take().is_some() {
+            tracing::info!("RPC {} is healthy again", self.transport.url());
+        }
+    }
+
+    fn mark_unhealthy(&self, cooldown: Duration, reason: impl std::fmt::Display) {
+        tracing::warn!(
+            "RPC {} failed: {reason}, skipping it for {} seconds",
+            self.transport.url(),
+            cooldown.as_secs()
+        );
+
+        *self.unhealthy_until.lock().unwrap() = Some(Instant::now() + cooldown);
+    }
+}
+
+fn is_rate_limited(res: &ResponsePacket) -> bool {
+    res.iter_errors().any(|e| e.is_retry_err())
+}
+
+impl Service<RequestPacket> for FailoverTransport {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: RequestPacket) -> Self::Future {
+        let inner = Arc::clone(&self.inner);
+        Box::pin(async move { inner.send(req).await })
+    }
+}
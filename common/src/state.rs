@@ -1,6 +1,7 @@
 use std::fmt::Display;
+use strum::EnumIter;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, EnumIter)]
 pub enum Dex {
     Ambient,
     Bean,
@@ -0,0 +1,84 @@
+mod harness;
+
+use alloy::{
+    primitives::{Address, Bytes, U256, utils::parse_ether},
+    sol,
+    sol_types::SolCall,
+};
+use alloy_chains::NamedChain;
+use common::onchain::token::{Token, balances_of};
+use harness::{call_data, mock_rpc, provider};
+use serde_json::json;
+use wiremock::MockServer;
+
+sol! {
+    struct Call {
+        address target;
+        bytes callData;
+    }
+
+    function aggregate(Call[] calls) returns (uint256 blockNumber, bytes[] returnData);
+
+    function balanceOf(address account) returns (uint256);
+}
+
+const OWNER: Address = Address::repeat_byte(0x42);
+
+/// The balance `OWNER` holds of the token at an address.
+fn balance(address: Address) -> U256 {
+    match address {
+        a if a == Token::USDC.address() => U256::from(5_000_000),
+        a if a == Token::SHMON.address() => parse_ether("2").unwrap(),
+        _ => U256::ZERO,
+    }
+}
+
+/// A node that holds 1 native token for `OWNER` and runs multicalls of `balanceOf`.
+async fn rpc() -> MockServer {
+    mock_rpc(|method, params| match method {
+        "eth_getBalance" => {
+            let owner: Address = params[0].as_str().unwrap().parse().unwrap();
+            assert_eq!(owner, OWNER);
+            json!(parse_ether("1").unwrap())
+        }
+        "eth_call" => {
+            let call = aggregateCall::abi_decode(&call_data(params), false).unwrap();
+
+            let results = call
+                .calls
+                .iter()
+                .map(|call| {
+                    let owner = balanceOfCall::abi_decode(&call.callData, false).unwrap();
+                    assert_eq!(owner.account, OWNER);
+                    balanceOfCall::abi_encode_returns(&(balance(call.target),)).into()
+                })
+                .collect::<Vec<Bytes>>();
+
+            json!(Bytes::from(aggregateCall::abi_encode_returns(&(U256::from(1), results))))
+        }
+        method => panic!("unexpected call to {method:?}"),
+    })
+    .await
+}
+
+#[tokio::test]
+async fn balances_include_every_token_of_the_chain() {
+    let server = rpc().await;
+    let provider = provider(&server.uri());
+
+    let balances = balances_of(&provider, NamedChain::MonadTestnet, OWNER).await.unwrap();
+    assert_eq!(
+        balances,
+        vec![
+            (Token::MON, parse_ether("1").unwrap()),
+            (Token::USDC, U256::from(5_000_000)),
+            (Token::WMON, U256::ZERO),
+            (Token::SHMON, parse_ether("2").unwrap()),
+            (Token::APRMON, U256::ZERO),
+        ]
+    );
+
+    // Base only has its native token
+    let balances = balances_of(&provider, NamedChain::Base, OWNER).await.unwrap();
+    assert_eq!(balances, vec![(Token::ETH, parse_ether("1").unwrap())]);
+}
@@ -0,0 +1,152 @@
+use common::{
+    Error,
+    config::{Config, ConfigError, NonceManagerKind, RpcEndpoint},
+};
+use std::time::Duration;
+
+const SHIPPED: &str = include_str!("../../data/config.toml");
+
+/// A config written before the RPC failover, without any of the settings added since then.
+const BEFORE_FAILOVER: &str = r#"
+ambient_swap_count = [0, 0]
+hashflow_swap_count = [0, 0]
+bean_swap_count = [0, 0]
+apriori_deposit_count = [0, 0]
+kinza_deposit_count = [0, 0]
+shmonad_deposit_count = [0, 0]
+nad_domains_count = [0, 0]
+need_bridge = true
+bridge_amount_range = [0.0001, 0.0002]
+thread_delay = [1000, 2000]
+restart_thread_delay = 5
+action_delay = [5, 5]
+deposit_ratio = [1, 2]
+swap_ratio = [5, 10]
+monad_rpc_url = "https://testnet-rpc.monad.xyz"
+base_rpc_url = "https://base.drpc.org"
+"#;
+
+/// The config without the table and the settings under it, up to the next blank line.
+fn without_table(config: &str, table: &str) -> String {
+    let start = config.find(&format!("[{table}]\n")).expect("table to be in the config");
+    let end = config[start..].find("\n\n").map_or(config.len(), |end| start + end);
+
+    format!("{}{}", &config[..start], &config[end..])
+}
+
+fn urls(endpoints: &[RpcEndpoint]) -> Vec<&str> {
+    endpoints.iter().map(RpcEndpoint::url).collect()
+}
+
+#[test]
+fn shipped_config_is_valid() {
+    Config::parse(SHIPPED).unwrap();
+}
+
+#[test]
+fn rejects_bps_above_one_hundred_percent() {
+    let config = SHIPPED.replacen(
+        "[bean_slippage]\nslippage_bps = 100",
+        "[bean_slippage]\nslippage_bps = 10001",
+        1,
+    );
+
+    match Config::parse(&config) {
+        Err(Error::Config(ConfigError::BpsOutOfRange { setting, field, value })) => {
+            assert_eq!((setting, field, value), ("bean_slippage", "slippage_bps", 10_001));
+        }
+        res => panic!("expected a config error, got {res:?}"),
+    }
+}
+
+#[test]
+fn rejects_slippage_on_firm_hashflow_quotes() {
+    let config = SHIPPED.replacen(
+        "[hashflow_slippage]\nslippage_bps = 0",
+        "[hashflow_slippage]\nslippage_bps = 50",
+        1,
+    );
+
+    assert!(matches!(
+        Config::parse(&config),
+        Err(Error::Config(ConfigError::FirmQuoteSlippage(50)))
+    ));
+}
+
+#[test]
+fn rejects_a_max_tps_that_is_not_positive() {
+    for value in ["0.0", "-1.0", "nan"] {
+        let config =
+            SHIPPED.replacen("# base_max_tps = 1.0", &format!("base_max_tps = {value}"), 1);
+
+        match Config::parse(&config) {
+            Err(Error::Config(ConfigError::InvalidMaxTps { setting, value })) => {
+                assert_eq!(setting, "base_max_tps");
+                assert!(value.is_nan() || value <= 0.0);
+            }
+            res => panic!("expected a config error for {value}, got {res:?}"),
+        }
+    }
+
+    let config = SHIPPED.replacen("# monad_max_tps = 2.0", "monad_max_tps = 0.5", 1);
+    assert_eq!(Config::parse(&config).unwrap().monad_max_tps, Some(0.5));
+}
+
+#[test]
+fn zero_turns_the_rpc_health_checks_off() {
+    let config = Config::parse(SHIPPED).unwrap();
+    assert_eq!(config.rpc_health_check_interval(), Some(Duration::from_secs(60)));
+
+    let config =
+        SHIPPED.replacen("rpc_health_check_interval = 60", "rpc_health_check_interval = 0", 1);
+    assert_eq!(Config::parse(&config).unwrap().rpc_health_check_interval(), None);
+}
+
+#[test]
+fn reads_configs_from_before_the_failover_support() {
+    let config = Config::parse(BEFORE_FAILOVER).unwrap();
+
+    assert_eq!(urls(&config.monad_rpc_urls), ["https://testnet-rpc.monad.xyz"]);
+    assert_eq!(urls(&config.base_rpc_urls), ["https://base.drpc.org"]);
+    assert_eq!((config.rpc_cooldown, config.rpc_health_check_interval), (30, 60));
+    assert_eq!((config.monad_max_tps, config.base_max_tps), (None, None));
+    assert!(matches!(config.nonce_manager, NonceManagerKind::Strict));
+    assert_eq!(config.ambient_slippage.slippage_bps, 100);
+    assert_eq!(config.monad_fee_policy.max_fee, None);
+    assert_eq!(config.retry_policy.permanent, 0);
+}
+
+#[test]
+fn nonce_manager_defaults_to_strict() {
+    let config = SHIPPED.replacen("nonce_manager = \"strict\"\n", "", 1);
+
+    let config = Config::parse(&config).unwrap();
+    assert!(matches!(config.nonce_manager, NonceManagerKind::Strict));
+}
+
+#[test]
+fn fee_policies_default_to_the_estimated_fees() {
+    let config = without_table(&without_table(SHIPPED, "monad_fee_policy"), "base_fee_policy");
+
+    let config = Config::parse(&config).unwrap();
+    for policy in [config.monad_fee_policy, config.base_fee_policy] {
+        assert_eq!(policy.multiplier, 1.0);
+        assert_eq!((policy.priority_fee, policy.max_fee), (None, None));
+        assert_eq!(policy.receipt_timeout(), None);
+    }
+}
+
+#[test]
+fn slippage_defaults_to_one_percent_without_an_impact_limit() {
+    let config = ["ambient_slippage", "bean_slippage", "hashflow_slippage"]
+        .into_iter()
+        .fold(SHIPPED.to_string(), |config, table| without_table(&config, table));
+
+    let config = Config::parse(&config).unwrap();
+    for (slippage, slippage_bps) in
+        [(config.ambient_slippage, 100), (config.bean_slippage, 100), (config.hashflow_slippage, 0)]
+    {
+        assert_eq!(slippage.slippage_bps, slippage_bps);
+        assert_eq!(slippage.max_price_impact_bps, 10_000);
+    }
+}
@@ -0,0 +1,542 @@
+mod harness;
+
+use alloy::{
+    consensus::Transaction as _,
+    hex,
+    primitives::{Address, Bytes, U256, utils::parse_ether},
+    providers::ext::AnvilApi,
+    sol,
+    sol_types::SolCall,
+};
+use alloy_chains::NamedChain;
+use common::{
+    Error,
+    config::Slippage,
+    onchain::{
+        action::{Action, ActionContext, ActionRecord},
+        dapps::{
+            ambient, apriori, bean, common::SwapError, gas_zip, hashflow, kinza, nad_domains,
+            shmonad, wmon,
+        },
+        token::Token,
+    },
+};
+use harness::{Harness, call_data, client, config, contains, mock_rpc, selector};
+use serde_json::{Value, json};
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{method, path},
+};
+
+const SLIPPAGE: Slippage = Slippage { slippage_bps: 100, max_price_impact_bps: 10_000 };
+
+sol! {
+    function getAmountsOut(uint256 amountIn, address[] path) returns (uint256[] amounts);
+}
+
+#[tokio::test]
+#[ignore = "needs anvil"]
+async fn gas_zip_bridge_sends_value_with_call_data() {
+    let h = Harness::spawn(NamedChain::Base).await;
+    let amount = parse_ether("0.0001").unwrap();
+
+    let outcome = gas_zip::bridge(&h.client, amount).await.unwrap();
+    assert!(outcome.is_success());
+
+    let tx = h.transaction(&outcome).await;
+    assert_eq!(tx.to(), Some(gas_zip::GAS_ZIP_CA));
+    assert_eq!(tx.value(), amount);
+    assert_eq!(tx.input().as_ref(), hex!("0101b1"));
+}
+
+#[tokio::test]
+#[ignore = "needs anvil"]
+async fn gas_zip_bridge_action_skips_short_balance() {
+    let h = Harness::spawn(NamedChain::Base).await;
+    h.client.provider.anvil_set_balance(h.address(), U256::ZERO).await.unwrap();
+
+    let (config, http_client) = (config(), reqwest::Client::new());
+    let ctx = ActionContext {
+        client: &h.client,
+        http_client: &http_client,
+        config: &config,
+        reserve: U256::ZERO,
+        history: &[],
+    };
+    let mut record = ActionRecord::default();
+
+    let outcome = gas_zip::GasZipBridge.execute(&ctx, &mut record).await.unwrap();
+    assert!(outcome.is_none());
+    assert!(record.amount_in.is_none());
+}
+
+#[tokio::test]
+#[ignore = "needs anvil"]
+async fn apriori_deposit_calls_aprmon() {
+    let h = Harness::spawn(NamedChain::MonadTestnet).await;
+    let amount = parse_ether("0.1").unwrap();
+
+    let outcome = apriori::deposit(&h.client, amount).await.unwrap();
+    assert!(outcome.is_success());
+
+    let tx = h.transaction(&outcome).await;
+    assert_eq!(tx.to(), Some(Token::APRMON.address()));
+    assert_eq!(tx.value(), amount);
+    assert_eq!(tx.input()[..4], selector("deposit(uint256,address)"));
+    assert_eq!(U256::from_be_slice(&tx.input()[4..36]), amount);
+    assert_eq!(Address::from_slice(&tx.input()[48..68]), h.address());
+}
+
+#[tokio::test]
+#[ignore = "needs anvil"]
+async fn kinza_deposit_action_records_deposited_share() {
+    let h = Harness::spawn(NamedChain::MonadTestnet).await;
+    let balance = h.client.get_native_balance().await.unwrap();
+
+    let (config, http_client) = (config(), reqwest::Client::new());
+    let ctx = ActionContext {
+        client: &h.client,
+        http_client: &http_client,
+        config: &config,
+        reserve: U256::ZERO,
+        history: &[],
+    };
+    assert!(kinza::KinzaDeposit.is_available(&ctx).await.unwrap());
+
+    let mut record = ActionRecord::default();
+    let outcome = kinza::KinzaDeposit.execute(&ctx, &mut record).await.unwrap().unwrap();
+    assert!(outcome.is_success());
+
+    let tx = h.transaction(&outcome).await;
+    assert_eq!(record.token_in, Some(Token::MON));
+    assert_eq!(record.amount_in, Some(tx.value()));
+
+    let [min, max] = config.deposit_ratio.map(U256::from);
+    assert!(tx.value() >= balance * min / U256::from(100));
+    assert!(tx.value() <= balance * max / U256::from(100));
+}
+
+#[tokio::test]
+#[ignore = "needs anvil"]
+async fn shmonad_deposit_calls_shmon() {
+    let h = Harness::spawn(NamedChain::MonadTestnet).await;
+    let amount = parse_ether("0.1").unwrap();
+
+    let outcome = shmonad::deposit(&h.client, amount).await.unwrap();
+    assert!(outcome.is_success());
+
+    let tx = h.transaction(&outcome).await;
+    assert_eq!(tx.to(), Some(Token::SHMON.address()));
+    assert_eq!(tx.value(), amount);
+    assert_eq!(tx.input()[..4], selector("deposit(uint256,address)"));
+    assert_eq!(Address::from_slice(&tx.input()[48..68]), h.address());
+}
+
+#[tokio::test]
+#[ignore = "needs anvil"]
+async fn shmonad_redeem_calls_shmon() {
+    let h = Harness::spawn(NamedChain::MonadTestnet).await;
+    let shares = parse_ether("0.1").unwrap();
+
+    let outcome = shmonad::redeem(&h.client, shares).await.unwrap();
+    assert!(outcome.is_success());
+
+    let tx = h.transaction(&outcome).await;
+    assert_eq!(tx.to(), Some(Token::SHMON.address()));
+    assert_eq!(tx.value(), U256::ZERO);
+    assert_eq!(tx.input()[..4], selector("redeem(uint256,address,address)"));
+    assert_eq!(U256::from_be_slice(&tx.input()[4..36]), shares);
+    assert_eq!(Address::from_slice(&tx.input()[48..68]), h.address());
+    assert_eq!(Address::from_slice(&tx.input()[80..100]), h.address());
+}
+
+#[tokio::test]
+#[ignore = "needs anvil"]
+async fn wmon_wrap_and_unwrap_call_wmon() {
+    let h = Harness::spawn(NamedChain::MonadTestnet).await;
+    let amount = parse_ether("0.1").unwrap();
+
+    let outcome = wmon::wrap(&h.client, amount).await.unwrap();
+    assert!(outcome.is_success());
+
+    let tx = h.transaction(&outcome).await;
+    assert_eq!(tx.to(), Some(Token::WMON.address()));
+    assert_eq!(tx.value(), amount);
+    assert_eq!(tx.input()[..], selector("deposit()"));
+
+    let outcome = wmon::unwrap(&h.client, amount).await.unwrap();
+    assert!(outcome.is_success());
+
+    let tx = h.transaction(&outcome).await;
+    assert_eq!(tx.to(), Some(Token::WMON.address()));
+    assert_eq!(tx.value(), U256::ZERO);
+    assert_eq!(tx.input()[..4], selector("withdraw(uint256)"));
+    assert_eq!(U256::from_be_slice(&tx.input()[4..36]), amount);
+}
+
+#[tokio::test]
+#[ignore = "needs anvil"]
+async fn apriori_request_withdraw_calls_aprmon() {
+    let h = Harness::spawn(NamedChain::MonadTestnet).await;
+    let shares = parse_ether("0.1").unwrap();
+
+    let outcome = apriori::request_withdraw(&h.client, shares).await.unwrap();
+    assert!(outcome.is_success());
+
+    let tx = h.transaction(&outcome).await;
+    assert_eq!(tx.to(), Some(Token::APRMON.address()));
+    assert_eq!(tx.input()[..4], selector("requestRedeem(uint256,address,address)"));
+    assert_eq!(U256::from_be_slice(&tx.input()[4..36]), shares);
+}
+
+#[tokio::test]
+#[ignore = "needs anvil"]
+async fn kinza_deposit_calls_gateway() {
+    let h = Harness::spawn(NamedChain::MonadTestnet).await;
+    let amount = parse_ether("0.1").unwrap();
+
+    let outcome = kinza::deposit(&h.client, amount).await.unwrap();
+    assert!(outcome.is_success());
+
+    let tx = h.transaction(&outcome).await;
+    assert_eq!(tx.to(), Some(kinza::KINZA_CA));
+    assert_eq!(tx.value(), amount);
+    assert_eq!(tx.input()[..4], selector("depositETH(address,address,uint16)"));
+    assert_eq!(Address::from_slice(&tx.input()[16..36]), Token::WMON.address());
+    assert_eq!(Address::from_slice(&tx.input()[48..68]), h.address());
+}
+
+#[tokio::test]
+#[ignore = "needs anvil"]
+async fn hashflow_swap_trades_rfq_quote() {
+    let h = Harness::spawn(NamedChain::MonadTestnet).await;
+    let amount = parse_ether("0.1").unwrap();
+    let txid = [0xab; 32];
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/client/v3/rfq"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "quotes": [{
+                "quoteData": {
+                    "quoteTokenAmount": "1000000",
+                    "pool": Address::repeat_byte(0x11),
+                    "txid": hex::encode_prefixed(txid),
+                    "nonce": 1,
+                    "quoteExpiry": 4102444800u64,
+                },
+                "signature": hex::encode_prefixed([0x22; 65]),
+            }]
+        })))
+        // without a price impact limit no reference is quoted on Bean
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let outcome = hashflow::swap(
+        &h.client,
+        reqwest::Client::new(),
+        &server.uri(),
+        Token::MON,
+        Token::USDC,
+        amount,
+        &SLIPPAGE,
+        &mut ActionRecord::default(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert!(outcome.is_success());
+
+    let requests = server.received_requests().await.unwrap();
+    let body: Value = serde_json::from_slice(&requests[0].body).unwrap();
+    let rfq = &body["rfqs"][0];
+    assert_eq!(rfq["baseTokenAmount"], amount.to_string());
+    assert_eq!(rfq["baseToken"].as_str().unwrap().parse::<Address>().unwrap(), Address::ZERO);
+    assert_eq!(rfq["trader"].as_str().unwrap().parse::<Address>().unwrap(), h.address());
+
+    let tx = h.transaction(&outcome).await;
+    assert_eq!(tx.to(), Some(hashflow::HASHFLOW_CA));
+    assert_eq!(tx.value(), amount);
+    assert!(contains(tx.input(), &txid));
+}
+
+#[tokio::test]
+async fn hashflow_swap_rejects_price_impact() {
+    // the node only answers the Bean quote the RFQ quote is compared to, the swap is never sent
+    let rpc = mock_rpc(|method, params| match method {
+        "eth_call" => {
+            let call = getAmountsOutCall::abi_decode(&call_data(params), false).unwrap();
+            assert_eq!(call.path, [Token::WMON.address(), Token::USDC.address()]);
+
+            let amounts = vec![call.amountIn, U256::from(1_100_000)];
+            json!(Bytes::from(getAmountsOutCall::abi_encode_returns(&(amounts,))))
+        }
+        method => panic!("unexpected call to {method:?}"),
+    })
+    .await;
+    let client = client(&rpc.uri());
+
+    // the quote is about 9% below the one of Bean
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/client/v3/rfq"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "quotes": [{
+                "quoteData": {
+                    "quoteTokenAmount": "1000000",
+                    "pool": Address::repeat_byte(0x11),
+                    "txid": hex::encode_prefixed([0xab; 32]),
+                    "nonce": 1,
+                    "quoteExpiry": 4102444800u64,
+                },
+                "signature": hex::encode_prefixed([0x22; 65]),
+            }]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let slippage = Slippage { slippage_bps: 0, max_price_impact_bps: 300 };
+    let res = hashflow::swap(
+        &client,
+        reqwest::Client::new(),
+        &server.uri(),
+        Token::MON,
+        Token::USDC,
+        parse_ether("0.1").unwrap(),
+        &slippage,
+        &mut ActionRecord::default(),
+    )
+    .await;
+
+    assert!(matches!(res, Err(Error::Swap(SwapError::PriceImpactTooHigh { max_bps: 300, .. }))));
+}
+
+#[tokio::test]
+async fn hashflow_swap_fails_without_quote() {
+    let client = client("http://127.0.0.1:1");
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/client/v3/rfq"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "quotes": [] })))
+        .mount(&server)
+        .await;
+
+    let res = hashflow::swap(
+        &client,
+        reqwest::Client::new(),
+        &server.uri(),
+        Token::MON,
+        Token::USDC,
+        parse_ether("0.1").unwrap(),
+        &SLIPPAGE,
+        &mut ActionRecord::default(),
+    )
+    .await;
+
+    assert!(matches!(res, Err(Error::Swap(SwapError::NoQuote))));
+}
+
+#[tokio::test]
+#[ignore = "needs anvil"]
+async fn nad_domains_mint_registers_with_signature() {
+    let h = Harness::spawn(NamedChain::MonadTestnet).await;
+    let signature = [0x33; 65];
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/register/signature"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "signature": hex::encode_prefixed(signature),
+            "nonce": "0x1",
+            "deadline": "0xf4865700",
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let outcome =
+        nad_domains::mint(&h.client, reqwest::Client::new(), &server.uri()).await.unwrap();
+    assert!(outcome.is_success());
+
+    let requests = server.received_requests().await.unwrap();
+    let owner = requests[0]
+        .url
+        .query_pairs()
+        .find_map(|(k, v)| (k == "nameOwner").then(|| v.parse::<Address>().unwrap()));
+    assert_eq!(owner, Some(h.address()));
+
+    let tx = h.transaction(&outcome).await;
+    assert_eq!(tx.to(), Some(nad_domains::NAD_DOMAINS_CA));
+    assert_eq!(tx.value(), parse_ether("0.02").unwrap());
+    assert_eq!(
+        tx.input()[..4],
+        selector(
+            "registerWithSignature((string,address,bool,address,bytes32,bytes,uint256,uint256),bytes)"
+        )
+    );
+    assert!(contains(tx.input(), &signature));
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and MONAD_STATE_DUMP or MONAD_FORK_URL"]
+async fn ambient_swap_mon_to_usdc() {
+    let h = Harness::spawn_forked().await;
+
+    let mut record = ActionRecord::default();
+    let outcome = ambient::swap(
+        &h.client,
+        parse_ether("0.1").unwrap(),
+        Token::MON,
+        Token::USDC,
+        &SLIPPAGE,
+        &mut record,
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert!(outcome.is_success());
+    // MON needs no approval
+    assert!(record.sent.is_empty());
+
+    let tx = h.transaction(&outcome).await;
+    assert_eq!(tx.to(), Some(ambient::DEX_CA));
+    assert!(h.token_balance(Token::USDC).await > U256::ZERO);
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and MONAD_STATE_DUMP or MONAD_FORK_URL"]
+async fn ambient_swap_usdc_to_mon_approves_first() {
+    let h = Harness::spawn_forked().await;
+
+    let mut record = ActionRecord::default();
+    ambient::swap(
+        &h.client,
+        parse_ether("0.1").unwrap(),
+        Token::MON,
+        Token::USDC,
+        &SLIPPAGE,
+        &mut record,
+    )
+    .await
+    .unwrap();
+    let usdc = h.token_balance(Token::USDC).await;
+
+    let outcome = ambient::swap(&h.client, usdc, Token::USDC, Token::MON, &SLIPPAGE, &mut record)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(outcome.is_success());
+    assert_eq!(h.token_balance(Token::USDC).await, U256::ZERO);
+    assert_approved(&record, Token::USDC, usdc);
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and MONAD_STATE_DUMP or MONAD_FORK_URL"]
+async fn bean_swap_mon_to_usdc() {
+    let h = Harness::spawn_forked().await;
+
+    let mut record = ActionRecord::default();
+    let outcome = bean::swap(
+        &h.client,
+        parse_ether("0.1").unwrap(),
+        Token::MON,
+        Token::USDC,
+        &SLIPPAGE,
+        &mut record,
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert!(outcome.is_success());
+    // MON needs no approval
+    assert!(record.sent.is_empty());
+
+    let tx = h.transaction(&outcome).await;
+    assert_eq!(tx.to(), Some(bean::BEAN_ROUTER));
+    assert!(h.token_balance(Token::USDC).await > U256::ZERO);
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and MONAD_STATE_DUMP or MONAD_FORK_URL"]
+async fn bean_swap_usdc_to_mon_approves_first() {
+    let h = Harness::spawn_forked().await;
+
+    let mut record = ActionRecord::default();
+    bean::swap(
+        &h.client,
+        parse_ether("0.1").unwrap(),
+        Token::MON,
+        Token::USDC,
+        &SLIPPAGE,
+        &mut record,
+    )
+    .await
+    .unwrap();
+    let usdc = h.token_balance(Token::USDC).await;
+
+    let outcome = bean::swap(&h.client, usdc, Token::USDC, Token::MON, &SLIPPAGE, &mut record)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(outcome.is_success());
+    assert_eq!(h.token_balance(Token::USDC).await, U256::ZERO);
+    assert_approved(&record, Token::USDC, usdc);
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and MONAD_STATE_DUMP or MONAD_FORK_URL"]
+async fn lending_deposits_mint_shares() {
+    let h = Harness::spawn_forked().await;
+    let amount = parse_ether("0.1").unwrap();
+
+    assert!(apriori::deposit(&h.client, amount).await.unwrap().is_success());
+    assert!(h.token_balance(Token::APRMON).await > U256::ZERO);
+
+    assert!(shmonad::deposit(&h.client, amount).await.unwrap().is_success());
+    assert!(h.token_balance(Token::SHMON).await > U256::ZERO);
+
+    assert!(kinza::deposit(&h.client, amount).await.unwrap().is_success());
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and MONAD_STATE_DUMP or MONAD_FORK_URL"]
+async fn lending_withdrawals_burn_shares() {
+    let h = Harness::spawn_forked().await;
+    let amount = parse_ether("0.1").unwrap();
+
+    assert!(shmonad::deposit(&h.client, amount).await.unwrap().is_success());
+    let shares = h.token_balance(Token::SHMON).await;
+    assert!(shmonad::redeem(&h.client, shares).await.unwrap().is_success());
+    assert_eq!(h.token_balance(Token::SHMON).await, U256::ZERO);
+
+    assert!(apriori::deposit(&h.client, amount).await.unwrap().is_success());
+    let shares = h.token_balance(Token::APRMON).await;
+    let request = apriori::request_withdraw(&h.client, shares).await.unwrap();
+    assert!(request.is_success());
+    assert_eq!(h.token_balance(Token::APRMON).await, U256::ZERO);
+
+    let deposit = kinza::deposit(&h.client, amount).await.unwrap();
+    let ktoken = kinza::position_token(&h.client, &[deposit.hash]).await.unwrap().unwrap();
+    let balance = h.client.get_token_balance(ktoken).await.unwrap();
+    let mut record = ActionRecord::default();
+    let outcome = kinza::withdraw(&h.client, ktoken, balance, &mut record).await.unwrap().unwrap();
+    assert!(outcome.is_success());
+    assert_eq!(record.sent.len(), 1);
+    assert_eq!(record.sent[0].kind, "approve");
+    assert!(record.sent[0].outcome.is_success());
+    assert!(h.client.get_token_balance(ktoken).await.unwrap() < balance);
+}
+
+/// Checks that the only transaction sent before the swap is the approval of the token swapped.
+fn assert_approved(record: &ActionRecord, token: Token, amount: U256) {
+    assert_eq!(record.sent.len(), 1);
+    let approval = &record.sent[0];
+    assert_eq!(
+        (approval.kind, approval.token_in, approval.amount_in),
+        ("approve", Some(token), Some(amount))
+    );
+    assert!(approval.outcome.is_success());
+}
@@ -0,0 +1,51 @@
+mod harness;
+
+use common::error::ErrorClass;
+use harness::{client, mock_rpc_responding};
+use serde_json::{Value, json};
+use wiremock::{MockServer, ResponseTemplate};
+
+fn error_response(code: i64, message: &str) -> ResponseTemplate {
+    let body: Value =
+        json!({ "jsonrpc": "2.0", "id": 0, "error": { "code": code, "message": message } });
+    ResponseTemplate::new(200).set_body_json(body)
+}
+
+async fn class_of(server: &MockServer) -> ErrorClass {
+    client(&server.uri()).get_native_balance().await.unwrap_err().class()
+}
+
+#[tokio::test]
+async fn classifies_insufficient_funds() {
+    let server = mock_rpc_responding(error_response(
+        -32000,
+        "insufficient funds for gas * price + value: have 0 want 1",
+    ))
+    .await;
+
+    assert_eq!(class_of(&server).await, ErrorClass::InsufficientBalance);
+}
+
+#[tokio::test]
+async fn classifies_revert() {
+    let server = mock_rpc_responding(error_response(3, "execution reverted: STF")).await;
+
+    assert_eq!(class_of(&server).await, ErrorClass::Revert);
+}
+
+#[tokio::test]
+async fn classifies_rate_limit() {
+    let server = mock_rpc_responding(ResponseTemplate::new(429)).await;
+    assert_eq!(class_of(&server).await, ErrorClass::RateLimit);
+
+    let server = mock_rpc_responding(error_response(-32005, "limit exceeded")).await;
+    assert_eq!(class_of(&server).await, ErrorClass::RateLimit);
+}
+
+#[tokio::test]
+async fn classifies_unreachable_rpc_as_transient() {
+    let client = client("http://127.0.0.1:1");
+
+    let class = client.get_native_balance().await.unwrap_err().class();
+    assert_eq!(class, ErrorClass::TransientRpc);
+}
@@ -0,0 +1,198 @@
+//! Shared setup for the integration tests.
+//!
+//! The dapp tests run against a local anvil node, so they are ignored by default and run with
+//! `cargo test -- --ignored` once `anvil` is installed. Without a state dump the chain is empty,
+//! so those tests only check what the dapps send. Tests that need the deployed contracts load the
+//! Monad testnet state from the file in `MONAD_STATE_DUMP` (see `anvil --dump-state`), or fork
+//! the RPC in `MONAD_FORK_URL`.
+//!
+//! The other tests answer the JSON-RPC requests from a [`mock_rpc`] server.
+
+// every test file only uses part of it
+#![allow(dead_code)]
+
+use alloy::{
+    hex,
+    network::Ethereum,
+    node_bindings::{Anvil, AnvilInstance},
+    primitives::{Address, U256, keccak256, utils::parse_ether},
+    providers::{Provider, ProviderBuilder, RootProvider, ext::AnvilApi},
+    rpc::types::Transaction,
+    signers::local::PrivateKeySigner,
+    sol,
+};
+use alloy_chains::NamedChain;
+use common::{
+    config::Config,
+    onchain::{client::Client, outcome::TxOutcome, token::Token},
+};
+use serde_json::{Value, json};
+use wiremock::{Mock, MockServer, Request, ResponseTemplate, matchers::method};
+
+pub const STATE_DUMP_VAR: &str = "MONAD_STATE_DUMP";
+pub const FORK_URL_VAR: &str = "MONAD_FORK_URL";
+
+enum State {
+    Empty,
+    Dump(String),
+    Fork(String),
+}
+
+sol! {
+    #[sol(rpc)]
+    interface IERC20Balance {
+        function balanceOf(address account) public view returns (uint256);
+    }
+}
+
+pub struct Harness {
+    pub client: Client<RootProvider>,
+    // the node is killed once the instance is dropped
+    _anvil: AnvilInstance,
+}
+
+impl Harness {
+    /// Spawns an empty chain.
+    ///
+    /// # Panics
+    ///
+    /// Panics if anvil is not installed.
+    pub async fn spawn(chain: NamedChain) -> Self {
+        Self::spawn_with_state(chain, State::Empty).await
+    }
+
+    /// Spawns a chain with the Monad testnet contracts.
+    ///
+    /// # Panics
+    ///
+    /// Panics if anvil is not installed, or if there is no state dump or fork url to take the
+    /// contracts from.
+    pub async fn spawn_forked() -> Self {
+        let state = match (std::env::var(STATE_DUMP_VAR), std::env::var(FORK_URL_VAR)) {
+            (Ok(dump), _) => State::Dump(dump),
+            (_, Ok(url)) => State::Fork(url),
+            _ => panic!("`{STATE_DUMP_VAR}` or `{FORK_URL_VAR}` must be set"),
+        };
+
+        Self::spawn_with_state(NamedChain::MonadTestnet, state).await
+    }
+
+    async fn spawn_with_state(chain: NamedChain, state: State) -> Self {
+        let anvil = Anvil::new().chain_id(chain as u64);
+
+        let anvil = match state {
+            State::Empty => anvil,
+            State::Dump(dump) => anvil.arg("--load-state").arg(dump),
+            State::Fork(url) => anvil.fork(url),
+        };
+
+        let anvil = anvil.try_spawn().expect("anvil to be installed");
+
+        let provider = ProviderBuilder::new()
+            .disable_recommended_fillers()
+            .network::<Ethereum>()
+            .with_chain(chain)
+            .on_http(anvil.endpoint_url());
+
+        let signer = PrivateKeySigner::from(anvil.keys()[0].clone());
+
+        // a loaded state doesn't necessarily fund the dev accounts
+        provider
+            .anvil_set_balance(signer.address(), parse_ether("1000").unwrap())
+            .await
+            .expect("anvil to set the balance");
+
+        Self { client: Client::new(signer, chain.into(), provider), _anvil: anvil }
+    }
+
+    pub fn address(&self) -> Address {
+        self.client.address()
+    }
+
+    /// Fetches the mined transaction behind the outcome.
+    pub async fn transaction(&self, outcome: &TxOutcome) -> Transaction {
+        self.client
+            .provider
+            .get_transaction_by_hash(outcome.hash)
+            .await
+            .expect("transaction to be fetched")
+            .expect("transaction to exist")
+    }
+
+    pub async fn token_balance(&self, token: Token) -> U256 {
+        IERC20Balance::new(token.address(), &self.client.provider)
+            .balanceOf(self.address())
+            .call()
+            .await
+            .expect("balance to be fetched")
+            ._0
+    }
+}
+
+/// A node that answers every JSON-RPC request with what `handler` returns for its method and
+/// params.
+pub async fn mock_rpc<F>(handler: F) -> MockServer
+where
+    F: Fn(&str, &Value) -> Value + Send + Sync + 'static,
+{
+    let server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .respond_with(move |request: &Request| {
+            let request: Value = serde_json::from_slice(&request.body).unwrap();
+            let result = handler(request["method"].as_str().unwrap(), &request["params"]);
+
+            ResponseTemplate::new(200)
+                .set_body_json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+        })
+        .mount(&server)
+        .await;
+
+    server
+}
+
+/// A node that answers every request with the same response, e.g. an error.
+pub async fn mock_rpc_responding(response: ResponseTemplate) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST")).respond_with(response).mount(&server).await;
+    server
+}
+
+/// The call data of the transaction in the params of an `eth_call`.
+pub fn call_data(params: &Value) -> Vec<u8> {
+    let tx = &params[0];
+    hex::decode(tx["input"].as_str().or(tx["data"].as_str()).unwrap()).unwrap()
+}
+
+/// A provider of the node at `url`. It has no fillers, so the node only sees the requests a
+/// test expects.
+pub fn provider(url: &str) -> RootProvider {
+    ProviderBuilder::new()
+        .disable_recommended_fillers()
+        .network::<Ethereum>()
+        .on_http(url.parse().unwrap())
+}
+
+/// A Monad client of the signer on the node at `url`.
+pub fn client_of(signer: PrivateKeySigner, url: &str) -> Client<RootProvider> {
+    Client::new(signer, NamedChain::MonadTestnet.into(), provider(url))
+}
+
+/// A Monad client of a random account on the node at `url`.
+pub fn client(url: &str) -> Client<RootProvider> {
+    client_of(PrivateKeySigner::random(), url)
+}
+
+/// The config shipped in `data/config.toml`.
+pub fn config() -> Config {
+    toml::from_str(include_str!("../../../data/config.toml")).expect("config to parse")
+}
+
+/// Returns the first four bytes of the keccak hash of a function signature.
+pub fn selector(signature: &str) -> [u8; 4] {
+    keccak256(signature)[..4].try_into().unwrap()
+}
+
+pub fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|window| window == needle)
+}
@@ -0,0 +1,77 @@
+mod harness;
+
+use alloy::{
+    primitives::{U256, utils::parse_ether},
+    providers::RootProvider,
+};
+use common::{
+    config::{FeePolicy, FeeTxType},
+    onchain::{
+        action::{ActionContext, ActionRecord, pick_deposit, preflight},
+        client::Client,
+        dapps::{kinza::KinzaDeposit, nad_domains::NadDomainsMint},
+    },
+};
+use harness::{config, mock_rpc};
+use serde_json::json;
+use wiremock::MockServer;
+
+const GAS_PRICE: u64 = 1_000_000_000;
+
+/// A node that quotes a gas price of 1 gwei and holds `balance` for every address.
+async fn rpc_with_balance(balance: U256) -> MockServer {
+    mock_rpc(move |method, _| match method {
+        "eth_gasPrice" => json!(U256::from(GAS_PRICE)),
+        "eth_getBalance" => json!(balance),
+        method => panic!("unexpected call to {method:?}"),
+    })
+    .await
+}
+
+fn client(url: &str) -> Client<RootProvider> {
+    let fee_policy =
+        FeePolicy { tx_type: FeeTxType::Legacy, gas_reserve: 0.1, ..Default::default() };
+
+    harness::client(url).with_fee_policy(fee_policy)
+}
+
+#[tokio::test]
+async fn preflight_keeps_reserve_and_gas_on_top_of_value() {
+    let config = config();
+    // the mint price, 500k gas at 1 gwei and the reserve
+    let required = parse_ether("0.1205").unwrap();
+
+    let server = rpc_with_balance(required - U256::from(1)).await;
+    let res = preflight(&NadDomainsMint, &client(&server.uri()), &config).await.unwrap();
+    assert!(res.is_none());
+
+    let server = rpc_with_balance(required).await;
+    let reserve = preflight(&NadDomainsMint, &client(&server.uri()), &config).await.unwrap();
+    assert_eq!(reserve, Some(parse_ether("0.1005").unwrap()));
+}
+
+#[tokio::test]
+async fn deposit_is_drawn_from_balance_above_reserve() {
+    let config = config();
+    let balance = parse_ether("1").unwrap();
+
+    let server = rpc_with_balance(balance).await;
+    let client = client(&server.uri());
+    let reserve = preflight(&KinzaDeposit, &client, &config).await.unwrap().unwrap();
+
+    let http_client = reqwest::Client::new();
+    let ctx = ActionContext {
+        client: &client,
+        http_client: &http_client,
+        config: &config,
+        reserve,
+        history: &[],
+    };
+    let mut record = ActionRecord::default();
+
+    let amount_in = pick_deposit("KINZA", &ctx, &mut record).await.unwrap();
+
+    let [_, max] = config.deposit_ratio.map(U256::from);
+    assert!(amount_in <= (balance - reserve) * max / U256::from(100));
+    assert_eq!(record.amount_in, Some(amount_in));
+}
@@ -0,0 +1,34 @@
+use common::onchain::rate_limit::TxRateLimiter;
+use std::time::{Duration, Instant};
+
+#[tokio::test]
+async fn spaces_out_slots() {
+    let limiter = TxRateLimiter::new(20.0);
+    let start = Instant::now();
+
+    for _ in 0..5 {
+        limiter.acquire().await;
+    }
+
+    // the first slot is free, the other four are 50ms apart
+    assert!(start.elapsed() >= Duration::from_millis(200));
+}
+
+#[tokio::test]
+async fn clones_share_slots() {
+    let limiter = TxRateLimiter::new(10.0);
+    let start = Instant::now();
+
+    let handles = (0..3)
+        .map(|_| {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.acquire().await })
+        })
+        .collect::<Vec<_>>();
+
+    for handle in handles {
+        handle.await.unwrap();
+    }
+
+    assert!(start.elapsed() >= Duration::from_millis(200));
+}
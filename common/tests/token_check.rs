@@ -0,0 +1,97 @@
+mod harness;
+
+use alloy::{
+    primitives::{Address, Bytes},
+    sol,
+    sol_types::SolCall,
+};
+use alloy_chains::NamedChain;
+use common::onchain::token::{Token, TokenMismatch, check_tokens};
+use harness::{call_data, mock_rpc, provider};
+use serde_json::json;
+use wiremock::MockServer;
+
+sol! {
+    struct Call {
+        address target;
+        bytes callData;
+    }
+
+    struct CallResult {
+        bool success;
+        bytes returnData;
+    }
+
+    function tryAggregate(bool requireSuccess, Call[] calls) returns (CallResult[] returnData);
+
+    function decimals() returns (uint8);
+
+    function symbol() returns (string);
+}
+
+/// What the contract at an address answers, if there's one.
+fn contract(address: Address) -> Option<(u8, &'static str)> {
+    match address {
+        a if a == Token::USDC.address() => Some((6, "usdc")),
+        a if a == Token::WMON.address() => Some((18, "WETH")),
+        a if a == Token::SHMON.address() => Some((8, "shMON")),
+        _ => None,
+    }
+}
+
+fn answer(call: &Call) -> CallResult {
+    let (decimals, symbol) = contract(call.target).unwrap();
+
+    let data = match call.callData[..4] == decimalsCall::SELECTOR {
+        true => decimalsCall::abi_encode_returns(&(decimals,)),
+        false => symbolCall::abi_encode_returns(&(symbol.to_string(),)),
+    };
+
+    CallResult { success: true, returnData: data.into() }
+}
+
+/// A node whose contracts are the ones of [`contract`] and that runs multicalls.
+async fn rpc() -> MockServer {
+    mock_rpc(|method, params| match method {
+        "eth_getCode" => {
+            let address: Address = params[0].as_str().unwrap().parse().unwrap();
+            let code = contract(address).map(|_| Bytes::from_static(&[0xfe]));
+            json!(code.unwrap_or_default())
+        }
+        "eth_call" => {
+            let call = tryAggregateCall::abi_decode(&call_data(params), false).unwrap();
+            let results = call.calls.iter().map(answer).collect::<Vec<_>>();
+            json!(Bytes::from(tryAggregateCall::abi_encode_returns(&(results,))))
+        }
+        method => panic!("unexpected call to {method:?}"),
+    })
+    .await
+}
+
+#[tokio::test]
+async fn reports_every_token_that_differs_from_its_contract() {
+    let server = rpc().await;
+    let provider = provider(&server.uri());
+
+    let mismatches = check_tokens(&provider, NamedChain::MonadTestnet).await.unwrap();
+
+    // USDC matches, and the symbol is compared ignoring case
+    assert_eq!(
+        mismatches,
+        vec![
+            TokenMismatch::NoCode { token: Token::APRMON },
+            TokenMismatch::Symbol { token: Token::WMON, onchain: "WETH".to_string() },
+            TokenMismatch::Decimals { token: Token::SHMON, listed: 18, onchain: 8 },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn chain_without_contract_tokens_needs_no_calls() {
+    let server = rpc().await;
+    let provider = provider(&server.uri());
+
+    let mismatches = check_tokens(&provider, NamedChain::Base).await.unwrap();
+    assert!(mismatches.is_empty());
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
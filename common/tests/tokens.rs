@@ -0,0 +1,94 @@
+use alloy::primitives::address;
+use common::{
+    Error,
+    onchain::token::{Token, TokenError, TokenRegistry},
+};
+
+const SHIPPED: &str = include_str!("../../data/tokens.toml");
+
+fn parse_err(s: &str) -> TokenError {
+    match TokenRegistry::parse(s) {
+        Err(Error::Token(e)) => e,
+        res => panic!("expected a token error, got {res:?}"),
+    }
+}
+
+#[test]
+fn shipped_tokens_are_valid() {
+    TokenRegistry::parse(SHIPPED).unwrap();
+
+    assert_eq!(Token::USDC.decimals(), 6);
+    assert_eq!(Token::WMON.address(), address!("0x760AfE86e5de5fa0Ee542fc7B7B713e1c5425701"));
+    assert!(Token::MON.is_native());
+    assert!(!Token::WMON.is_swap_allowed());
+    assert!(Token::USDC.is_supported_by("AMBIENT"));
+    assert_eq!(Token::from_ticker("shMON"), Some(Token::SHMON));
+}
+
+#[test]
+fn added_token_needs_no_code_change() {
+    let registry = format!(
+        r#"{SHIPPED}
+[[monad]]
+symbol = "DAK"
+address = "0x0F0BDEbF0F83cD1EE3974779Bcb7315f9808c714"
+decimals = 18
+swap = true
+dexes = ["BEAN"]
+"#
+    );
+
+    TokenRegistry::parse(&registry).unwrap();
+}
+
+#[test]
+fn rejects_invalid_tokens() {
+    let duplicate = format!(
+        r#"{SHIPPED}
+[[base]]
+symbol = "USDC"
+address = "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913"
+decimals = 6
+"#
+    );
+    assert!(
+        matches!(parse_err(&duplicate), TokenError::DuplicateSymbol(symbol) if symbol == "USDC")
+    );
+
+    let unknown_dex = SHIPPED.replacen(r#""HASHFLOW"]"#, r#""UNISWAP"]"#, 1);
+    assert!(
+        matches!(parse_err(&unknown_dex), TokenError::UnknownDex { dex, .. } if dex == "UNISWAP")
+    );
+
+    let not_native = SHIPPED.replacen("native = true\n", "", 1);
+    assert!(matches!(parse_err(&not_native), TokenError::NativeCount(_)));
+
+    let no_pool = format!(
+        r#"{SHIPPED}
+[[monad]]
+symbol = "DAK"
+address = "0x0F0BDEbF0F83cD1EE3974779Bcb7315f9808c714"
+decimals = 18
+swap = true
+dexes = ["AMBIENT"]
+"#
+    );
+    assert!(matches!(parse_err(&no_pool), TokenError::NoAmbientPool { to, .. } if to == "DAK"));
+
+    let no_reference = format!(
+        r#"{SHIPPED}
+[[monad]]
+symbol = "DAK"
+address = "0x0F0BDEbF0F83cD1EE3974779Bcb7315f9808c714"
+decimals = 18
+swap = true
+dexes = ["HASHFLOW"]
+"#
+    );
+    assert!(
+        matches!(parse_err(&no_reference), TokenError::NoHashflowReference(symbol) if symbol == "DAK")
+    );
+
+    let missing = SHIPPED.replacen(r#"symbol = "aprMON""#, r#"symbol = "stMON""#, 1);
+    assert!(matches!(parse_err(&missing), TokenError::Missing { symbol: "aprMON", .. }));
+}
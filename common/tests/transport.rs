@@ -0,0 +1,57 @@
+mod harness;
+
+use alloy::{
+    network::Ethereum,
+    providers::{Provider, ProviderBuilder, RootProvider},
+    rpc::client::RpcClient,
+};
+use common::{config::RpcEndpoint, onchain::transport::FailoverTransport};
+use harness::{mock_rpc, mock_rpc_responding};
+use serde_json::json;
+use std::time::Duration;
+use wiremock::{MockServer, ResponseTemplate};
+
+fn provider(urls: &[String]) -> RootProvider {
+    let endpoints = urls.iter().cloned().map(RpcEndpoint::Url).collect::<Vec<_>>();
+    let transport = FailoverTransport::new(&endpoints, Duration::from_secs(60)).unwrap();
+
+    ProviderBuilder::new()
+        .disable_recommended_fillers()
+        .network::<Ethereum>()
+        .on_client(RpcClient::new(transport, false))
+}
+
+async fn healthy_rpc() -> MockServer {
+    mock_rpc(|_, _| json!("0x2a")).await
+}
+
+#[tokio::test]
+async fn fails_over_from_rate_limited_rpc() {
+    let limited = mock_rpc_responding(ResponseTemplate::new(429)).await;
+
+    let healthy = healthy_rpc().await;
+    let provider = provider(&[limited.uri(), healthy.uri()]);
+
+    // the rate limited rpc is on a cooldown after its first failure, so it's hit once at most
+    for _ in 0..5 {
+        assert_eq!(provider.get_block_number().await.unwrap(), 42);
+    }
+
+    assert!(limited.received_requests().await.unwrap().len() <= 1);
+    assert_eq!(healthy.received_requests().await.unwrap().len(), 5);
+}
+
+#[tokio::test]
+async fn fails_over_from_unreachable_rpc() {
+    let healthy = healthy_rpc().await;
+    let provider = provider(&["http://127.0.0.1:1".to_string(), healthy.uri()]);
+
+    assert_eq!(provider.get_block_number().await.unwrap(), 42);
+}
+
+#[tokio::test]
+async fn returns_error_when_every_rpc_fails() {
+    let provider = provider(&["http://127.0.0.1:1".to_string()]);
+
+    assert!(provider.get_block_number().await.is_err());
+}
@@ -0,0 +1,122 @@
+mod harness;
+
+use alloy::{
+    primitives::{Address, B256, U256, address, keccak256},
+    signers::local::PrivateKeySigner,
+};
+use common::onchain::{
+    dapps::{apriori, kinza},
+    token::Token,
+};
+use harness::{call_data, client_of, mock_rpc};
+use serde_json::{Value, json};
+use wiremock::MockServer;
+
+const KTOKEN: Address = address!("0x00000000000000000000000000000000000000aa");
+const HASH: B256 = B256::repeat_byte(0x11);
+
+fn topic(address: Address) -> B256 {
+    address.into_word()
+}
+
+fn log(address: Address, topics: &[B256], data: Vec<u8>) -> Value {
+    json!({
+        "address": address,
+        "topics": topics,
+        "data": alloy::hex::encode_prefixed(data),
+        "blockHash": B256::repeat_byte(0x22),
+        "blockNumber": "0x1",
+        "transactionHash": HASH,
+        "transactionIndex": "0x0",
+        "logIndex": "0x0",
+        "removed": false,
+    })
+}
+
+fn receipt(logs: Vec<Value>) -> Value {
+    json!({
+        "type": "0x2",
+        "status": "0x1",
+        "transactionHash": HASH,
+        "transactionIndex": "0x0",
+        "blockHash": B256::repeat_byte(0x22),
+        "blockNumber": "0x1",
+        "from": Address::ZERO,
+        "to": Address::ZERO,
+        "contractAddress": null,
+        "cumulativeGasUsed": "0x5208",
+        "gasUsed": "0x5208",
+        "effectiveGasPrice": "0x1",
+        "logs": logs,
+        "logsBloom": alloy::hex::encode_prefixed([0u8; 256]),
+    })
+}
+
+/// A node that answers every receipt with `receipt` and every call with `call`, given the call
+/// data.
+async fn rpc(receipt: Value, call: fn(&[u8]) -> U256) -> MockServer {
+    mock_rpc(move |method, params| match method {
+        "eth_getTransactionReceipt" => receipt.clone(),
+        "eth_call" => json!(B256::from(call(&call_data(params)))),
+        method => panic!("unexpected call to {method:?}"),
+    })
+    .await
+}
+
+#[tokio::test]
+async fn kinza_position_token_is_the_token_minted_to_the_account() {
+    let transfer = keccak256("Transfer(address,address,uint256)");
+    let signer = PrivateKeySigner::random();
+    let amount = U256::from(100).to_be_bytes_vec();
+
+    let mint_to =
+        |to: Address| log(KTOKEN, &[transfer, topic(Address::ZERO), topic(to)], amount.clone());
+
+    // the gateway moves the wrapped MON into the pool, then the kToken is minted
+    let wrapped = log(
+        Token::WMON.address(),
+        &[transfer, topic(kinza::KINZA_CA), topic(KTOKEN)],
+        amount.clone(),
+    );
+
+    let server =
+        rpc(receipt(vec![wrapped.clone(), mint_to(Address::repeat_byte(1))]), |_| U256::ZERO).await;
+    let client = client_of(signer.clone(), &server.uri());
+    assert_eq!(kinza::position_token(&client, &[HASH]).await.unwrap(), None);
+
+    let server = rpc(receipt(vec![wrapped, mint_to(signer.address())]), |_| U256::ZERO).await;
+    let client = client_of(signer, &server.uri());
+    assert_eq!(kinza::position_token(&client, &[HASH]).await.unwrap(), Some(KTOKEN));
+}
+
+#[tokio::test]
+async fn apriori_claims_only_processed_requests() {
+    let redeem_request = keccak256("RedeemRequest(address,address,uint256,address,uint256)");
+    let signer = PrivateKeySigner::random();
+    let owner = topic(signer.address());
+
+    let request = |id: u64| {
+        let mut data = topic(signer.address()).to_vec();
+        data.extend(U256::from(10).to_be_bytes_vec());
+        log(
+            Token::APRMON.address(),
+            &[redeem_request, owner, owner, B256::from(U256::from(id))],
+            data,
+        )
+    };
+
+    // request 1 is processed, request 2 is not
+    let server = rpc(receipt(vec![request(1), request(2)]), |data| {
+        let id = U256::from_be_slice(&data[4..36]);
+        match id == U256::from(1) {
+            true => U256::from(10),
+            false => U256::ZERO,
+        }
+    })
+    .await;
+
+    let client = client_of(signer, &server.uri());
+
+    let ids = apriori::claimable_requests(&client, &[HASH]).await.unwrap();
+    assert_eq!(ids, vec![U256::from(1)]);
+}
@@ -0,0 +1,222 @@
+//! Encryption of the private keys stored in the account table.
+//!
+//! A key is stored as `enc:v1:<salt>:<nonce>:<ciphertext>`, encrypted with AES-256-GCM under a
+//! key derived from the passphrase with scrypt. Keys encrypted in the same run share a salt, so
+//! the derivation only runs once per salt.
+
+use aes_gcm::{Aes256Gcm, Key, KeyInit, Nonce, aead::Aead};
+use alloy::hex;
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+};
+use thiserror::Error;
+
+use crate::error::Result;
+
+/// Environment variable the passphrase is read from before falling back to a prompt.
+pub const PASSPHRASE_VAR: &str = "MONAD_PULSE_PASSPHRASE";
+
+const PREFIX: &str = "enc:v1:";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+// 2^15 rounds take about a tenth of a second
+const SCRYPT_LOG_N: u8 = 15;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+
+static CIPHER: OnceLock<KeyCipher> = OnceLock::new();
+
+#[derive(Error, Debug)]
+pub enum KeyError {
+    #[error("private keys are locked, no passphrase was given")]
+    Locked,
+
+    #[error("the passphrase doesn't match the one the private keys were encrypted with")]
+    WrongPassphrase,
+
+    #[error("encrypted private key is malformed")]
+    Malformed,
+
+    #[error("failed to derive the encryption key: {0}")]
+    Derivation(String),
+}
+
+struct KeyCipher {
+    passphrase: String,
+    salt: [u8; SALT_LEN],
+    keys: Mutex<HashMap<[u8; SALT_LEN], Key<Aes256Gcm>>>,
+}
+
+impl KeyCipher {
+    fn new(passphrase: String) -> Self {
+        Self { passphrase, salt: rand::random(), keys: Mutex::new(HashMap::new()) }
+    }
+
+    fn key(&self, salt: [u8; SALT_LEN]) -> Result<Key<Aes256Gcm>> {
+        let mut keys = self.keys.lock().unwrap();
+
+        if let Some(key) = keys.get(&salt) {
+            return Ok(*key);
+        }
+
+        let params = scrypt::Params::new(SCRYPT_LOG_N, SCRYPT_R, SCRYPT_P, 32)
+            .map_err(|e| KeyError::Derivation(e.to_string()))?;
+
+        let mut key = Key::<Aes256Gcm>::default();
+        scrypt::scrypt(self.passphrase.as_bytes(), &salt, &params, &mut key)
+            .map_err(|e| KeyError::Derivation(e.to_string()))?;
+
+        keys.insert(salt, key);
+        Ok(key)
+    }
+
+    fn encrypt(&self, pk: &str) -> Result<String> {
+        let key = self.key(self.salt)?;
+        let nonce: [u8; NONCE_LEN] = rand::random();
+
+        let ciphertext = Aes256Gcm::new(&key)
+            .encrypt(&Nonce::from(nonce), pk.as_bytes())
+            .map_err(|_| KeyError::Malformed)?;
+
+        Ok(format!(
+            "{PREFIX}{}:{}:{}",
+            hex::encode(self.salt),
+            hex::encode(nonce),
+            hex::encode(ciphertext)
+        ))
+    }
+
+    /// Decrypts a key stored with the [`PREFIX`].
+    fn decrypt(&self, encrypted: &str) -> Result<String> {
+        let parts = encrypted
+            .split(':')
+            .map(|part| hex::decode(part).map_err(|_| KeyError::Malformed))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let [salt, nonce, ciphertext] = parts.as_slice() else {
+            return Err(KeyError::Malformed.into());
+        };
+
+        let salt: [u8; SALT_LEN] = salt.as_slice().try_into().map_err(|_| KeyError::Malformed)?;
+        let nonce: [u8; NONCE_LEN] =
+            nonce.as_slice().try_into().map_err(|_| KeyError::Malformed)?;
+
+        let key = self.key(salt)?;
+        let pk = Aes256Gcm::new(&key)
+            .decrypt(&Nonce::from(nonce), ciphertext.as_slice())
+            .map_err(|_| KeyError::WrongPassphrase)?;
+
+        String::from_utf8(pk).map_err(|_| KeyError::Malformed.into())
+    }
+}
+
+/// Sets the passphrase the private keys are encrypted and decrypted with for the rest of the
+/// run. Only the first call has an effect.
+pub fn unlock(passphrase: String) {
+    let _ = CIPHER.get_or_init(|| KeyCipher::new(passphrase));
+}
+
+pub fn is_unlocked() -> bool {
+    CIPHER.get().is_some()
+}
+
+pub(crate) fn passphrase() -> Option<&'static str> {
+    CIPHER.get().map(|cipher| cipher.passphrase.as_str())
+}
+
+pub fn is_encrypted(stored: &str) -> bool {
+    stored.starts_with(PREFIX)
+}
+
+pub fn encrypt(pk: &str) -> Result<String> {
+    CIPHER.get().ok_or(KeyError::Locked)?.encrypt(pk)
+}
+
+/// Decrypts a stored private key. A key stored before encryption was introduced is returned
+/// as is.
+pub fn decrypt(stored: &str) -> Result<String> {
+    let Some(encrypted) = stored.strip_prefix(PREFIX) else {
+        return Ok(stored.to_string());
+    };
+
+    CIPHER.get().ok_or(KeyError::Locked)?.decrypt(encrypted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+
+    const PK: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    fn key_error(res: Result<String>) -> KeyError {
+        match res {
+            Err(Error::Key(e)) => e,
+            res => panic!("expected a key error, got {res:?}"),
+        }
+    }
+
+    /// Replaces the part of an encrypted key at `index`, 0 being the salt.
+    fn replace_part(stored: &str, index: usize, part: &str) -> String {
+        let mut parts = stored.strip_prefix(PREFIX).unwrap().split(':').collect::<Vec<_>>();
+        parts[index] = part;
+        format!("{PREFIX}{}", parts.join(":"))
+    }
+
+    #[test]
+    fn encrypted_key_decrypts_to_the_key() {
+        let cipher = KeyCipher::new("passphrase".to_string());
+
+        let stored = cipher.encrypt(PK).unwrap();
+        assert!(is_encrypted(&stored));
+        assert!(!stored.contains(&PK[2..]));
+        assert_eq!(cipher.decrypt(stored.strip_prefix(PREFIX).unwrap()).unwrap(), PK);
+
+        // every encryption has a nonce of its own
+        assert_ne!(cipher.encrypt(PK).unwrap(), stored);
+    }
+
+    #[test]
+    fn wrong_passphrase_fails_to_decrypt() {
+        let stored = KeyCipher::new("passphrase".to_string()).encrypt(PK).unwrap();
+        let other = KeyCipher::new("another passphrase".to_string());
+
+        let res = other.decrypt(stored.strip_prefix(PREFIX).unwrap());
+        assert!(matches!(key_error(res), KeyError::WrongPassphrase));
+    }
+
+    #[test]
+    fn tampered_key_fails_to_decrypt() {
+        let cipher = KeyCipher::new("passphrase".to_string());
+        let stored = cipher.encrypt(PK).unwrap();
+        let ciphertext = stored.rsplit(':').next().unwrap();
+
+        // a flipped bit of the ciphertext fails its authentication tag
+        let mut flipped = hex::decode(ciphertext).unwrap();
+        flipped[0] ^= 1;
+        let tampered = replace_part(&stored, 2, &hex::encode(flipped));
+        let res = cipher.decrypt(tampered.strip_prefix(PREFIX).unwrap());
+        assert!(matches!(key_error(res), KeyError::WrongPassphrase));
+
+        let short_nonce = replace_part(&stored, 1, "abcd");
+        let res = cipher.decrypt(short_nonce.strip_prefix(PREFIX).unwrap());
+        assert!(matches!(key_error(res), KeyError::Malformed));
+
+        let not_hex = replace_part(&stored, 0, "salt");
+        let res = cipher.decrypt(not_hex.strip_prefix(PREFIX).unwrap());
+        assert!(matches!(key_error(res), KeyError::Malformed));
+
+        let missing_part = format!("{PREFIX}{ciphertext}");
+        let res = cipher.decrypt(missing_part.strip_prefix(PREFIX).unwrap());
+        assert!(matches!(key_error(res), KeyError::Malformed));
+    }
+
+    #[test]
+    fn plain_key_is_returned_as_is() {
+        // a plain key needs no passphrase, so it's read even before the keys are unlocked
+        assert!(!is_encrypted(PK));
+        assert_eq!(decrypt(PK).unwrap(), PK);
+    }
+}
@@ -1,23 +1,33 @@
 use super::entity::impls::prelude::AccountActiveModel;
 use crate::{
-    entity::impls::account::NewActiveModelOptionsBuilder,
-    error::{Error, Result},
+    crypto,
+    entity::impls::account::{AccountConditions, NewActiveModelOptionsBuilder},
+    error::Result,
     repositories::RepoImpls,
-    use_cases::accounts,
-};
-use alloy::signers::local::PrivateKeySigner;
-use common::{
-    config::Config,
-    utils::{fs::read_lines, random::random_in_range},
+    use_cases::{account_goals, accounts},
 };
+use alloy::{hex, primitives::Address, signers::local::PrivateKeySigner};
+use common::{config::Config, utils::fs::read_lines};
 use migration::{Migrator, MigratorTrait};
-use sea_orm::{ConnectOptions, Database, DbConn};
-use std::{str::FromStr, sync::Arc};
+use sea_orm::{ActiveModelTrait, ConnectOptions, ConnectionTrait, Database, DbConn, Set};
+use std::{
+    collections::{HashMap, HashSet},
+    io::ErrorKind,
+    path::Path,
+    str::FromStr,
+    sync::Arc,
+};
 
-pub async fn connect() -> Result<DbConn> {
-    const DB_URL: &str = "sqlite://data/database.sqlite?mode=rwc";
+const DB_URL: &str = "sqlite://data/database.sqlite?mode=rwc";
+const SCRATCH_DB_PATH: &str = "data/database.dry_run.sqlite";
+const KEYSTORES_PATH: &str = "data/keystores";
+
+/// Environment variable with the password of the keystores in `data/keystores`. The passphrase
+/// of the database is tried when it isn't set.
+pub const KEYSTORE_PASSWORD_VAR: &str = "MONAD_PULSE_KEYSTORE_PASSWORD";
 
-    let mut options = ConnectOptions::new(DB_URL);
+async fn connect_to(url: &str) -> Result<DbConn> {
+    let mut options = ConnectOptions::new(url);
     options.sqlx_logging(false);
 
     let db = Database::connect(options).await?;
@@ -27,55 +37,364 @@ pub async fn connect() -> Result<DbConn> {
     Ok(db)
 }
 
-pub async fn generate(repo: Arc<RepoImpls>, config: Arc<Config>) -> Result<()> {
+pub async fn connect() -> Result<DbConn> {
+    connect_to(DB_URL).await
+}
+
+/// Connects to the database file at `path` instead of the one in `data`.
+pub async fn connect_at(path: &Path) -> Result<DbConn> {
+    connect_to(&format!("sqlite://{}?mode=rwc", path.display())).await
+}
+
+/// Copies the database behind `conn` to a scratch file and connects to the copy.
+///
+/// The previous scratch copy is overwritten.
+pub async fn connect_scratch(conn: &DbConn) -> Result<DbConn> {
+    if let Err(e) = tokio::fs::remove_file(SCRATCH_DB_PATH).await &&
+        e.kind() != ErrorKind::NotFound
+    {
+        return Err(e.into());
+    }
+
+    conn.execute_unprepared(&format!("VACUUM INTO '{SCRATCH_DB_PATH}'")).await?;
+
+    connect_to(&format!("sqlite://{SCRATCH_DB_PATH}?mode=rwc")).await
+}
+
+/// Checks that a private key is valid and returns the address it belongs to.
+pub(crate) fn parse_private_key(pk: &str) -> Result<Address> {
+    Ok(PrivateKeySigner::from_str(pk)?.address())
+}
+
+/// Checks that a proxy url can be used by the http client.
+pub(crate) fn check_proxy(proxy: &str) -> Result<()> {
+    reqwest::Proxy::all(proxy)?;
+    Ok(())
+}
+
+/// Reads the private keys from the JSON v3 keystores in `data/keystores`, if there are any.
+async fn read_keystores() -> Result<Vec<String>> {
+    let mut entries = match tokio::fs::read_dir(KEYSTORES_PATH).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e.into()),
+    };
+
+    let password = match std::env::var(KEYSTORE_PASSWORD_VAR) {
+        Ok(password) => password,
+        Err(_) => crypto::passphrase().ok_or(crypto::KeyError::Locked)?.to_string(),
+    };
+
+    let mut paths = vec![];
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut pks = vec![];
+    for path in paths {
+        match decrypt_keystore(&path, &password).await {
+            Ok(pk) => pks.push(pk),
+            Err(e) => tracing::error!("Keystore `{}` can't be decrypted: {e}", path.display()),
+        }
+    }
+
+    Ok(pks)
+}
+
+async fn decrypt_keystore(path: &Path, password: &str) -> Result<String> {
+    let (path, password) = (path.to_owned(), password.to_owned());
+
+    // scrypt keystores take a while to decrypt
+    let signer =
+        tokio::task::spawn_blocking(move || PrivateKeySigner::decrypt_keystore(path, password))
+            .await
+            .map_err(std::io::Error::other)??;
+
+    Ok(hex::encode_prefixed(signer.to_bytes()))
+}
+
+/// A private key from `data/private_keys.txt` or a keystore with the proxy paired with it.
+struct KeyEntry {
+    pk: String,
+    address: Address,
+    proxy: Option<String>,
+}
+
+/// Reads the private keys from `data/private_keys.txt` and the keystores in `data/keystores` and
+/// pairs them with the proxies in `data/proxies.txt` in order. Invalid keys are skipped, as are
+/// keys with an invalid proxy.
+async fn read_key_entries() -> Result<Vec<KeyEntry>> {
     const PRIVATE_KEYS_PATH: &str = "data/private_keys.txt";
     const PROXIES_PATH: &str = "data/proxies.txt";
 
-    let (pks, proxies) = tokio::try_join!(read_lines(PRIVATE_KEYS_PATH), read_lines(PROXIES_PATH))?;
+    let (mut pks, proxies) =
+        tokio::try_join!(read_lines(PRIVATE_KEYS_PATH), read_lines(PROXIES_PATH))?;
+    pks.extend(read_keystores().await?);
 
     let mut proxies_iter = proxies.into_iter();
+    let mut entries = vec![];
 
-    for pk in pks {
-        let address = match PrivateKeySigner::from_str(&pk) {
-            Ok(signer) => signer.address(),
+    for (i, pk) in pks.into_iter().enumerate() {
+        let address = match parse_private_key(&pk) {
+            Ok(address) => address,
             Err(e) => {
-                tracing::error!("Private key `{pk}` is invalid: {e}");
+                tracing::error!("Private key #{} is invalid: {e}", i + 1);
                 continue;
             }
         };
 
-        let opts = NewActiveModelOptionsBuilder::default()
-            .pk(pk.to_string())
-            .proxy(proxies_iter.next())
-            .address(address.to_string())
-            .target_ambient_swaps_count(random_in_range(config.ambient_swap_count))
-            .target_apriori_deposit_count(random_in_range(config.apriori_deposit_count))
-            .target_bean_swaps_count(random_in_range(config.bean_swap_count))
-            .target_hashflow_swaps_count(random_in_range(config.hashflow_swap_count))
-            .target_kinza_deposit_count(random_in_range(config.kinza_deposit_count))
-            .target_shmonad_deposit_count(random_in_range(config.shmonad_deposit_count))
-            .target_nad_domains_count(random_in_range(config.nad_domains_count))
-            .bridge_goal(!config.need_bridge)
-            .build()?;
-
-        let account = AccountActiveModel::new(opts);
-
-        if let Err(Error::Db(sea_orm::DbErr::Exec(rt_e))) =
-            accounts::add(repo.clone(), account).await
+        let proxy = proxies_iter.next();
+
+        if let Some(proxy) = &proxy &&
+            let Err(e) = check_proxy(proxy)
         {
-            if rt_e.to_string().contains("UNIQUE constraint failed") {
-                tracing::warn!("An attempt to insert a duplicate entry failed, private key: `{pk}`")
-            } else {
-                tracing::error!("{}", rt_e.to_string());
-            }
+            tracing::error!("Proxy `{proxy}` is invalid: {e}");
+            continue;
+        }
+
+        entries.push(KeyEntry { pk, address, proxy });
+    }
+
+    Ok(entries)
+}
+
+/// Inserts the account of the key with targets drawn from the config.
+async fn add_account(repo: Arc<RepoImpls>, entry: &KeyEntry, config: &Config) -> Result<()> {
+    let opts = NewActiveModelOptionsBuilder::default()
+        .pk(crypto::encrypt(&entry.pk)?)
+        .proxy(entry.proxy.clone())
+        .address(entry.address.to_string())
+        .bridge_goal(!config.need_bridge)
+        .build()?;
+
+    let id = accounts::add(repo.clone(), AccountActiveModel::new(opts)).await?;
+    account_goals::roll_targets(repo, id, config, true).await
+}
+
+/// Adds accounts from `data/private_keys.txt` and the keystores in `data/keystores`, pairing
+/// them with the proxies in `data/proxies.txt` in order.
+///
+/// Private keys are encrypted with the passphrase set by [`crypto::unlock`] before they're
+/// stored. Since every encryption of a key differs, a key that is stored already is recognized
+/// by its address and skipped.
+pub async fn generate(repo: Arc<RepoImpls>, config: Arc<Config>) -> Result<()> {
+    let mut stored = accounts::search(repo.clone(), AccountConditions::default())
+        .await?
+        .into_iter()
+        .map(|account| account.address)
+        .collect::<HashSet<_>>();
+
+    for entry in read_key_entries().await? {
+        let address = entry.address.to_string();
+
+        if !stored.insert(address.clone()) {
+            tracing::warn!("Private key of `{address}` is stored already, skipping it");
+            continue;
+        }
+
+        if let Err(e) = add_account(repo.clone(), &entry, &config).await {
+            tracing::error!("Failed to add `{address}`: {e}");
         }
     }
 
     Ok(())
 }
 
+#[derive(Debug, Default)]
+pub struct SyncSummary {
+    pub added: usize,
+    pub updated: usize,
+    pub deactivated: usize,
+    pub unchanged: usize,
+    /// New keys whose account couldn't be added, which are logged and left for the next sync.
+    pub failed: usize,
+}
+
+/// Brings the database in line with `data/private_keys.txt`, the keystores and
+/// `data/proxies.txt` without touching the progress of the accounts.
+///
+/// New keys are added and existing accounts get the proxy that is now paired with their key.
+/// An account whose key is gone from the files is marked inactive rather than deleted, so the
+/// warmup skips it until the key is listed again. Every listed account is active again, including
+/// one the warmup gave up on, and gets its retries back. With `reroll_targets` the targets of every
+/// listed account are drawn again from `config`, keeping the current counts.
+pub async fn sync(
+    repo: Arc<RepoImpls>,
+    config: Arc<Config>,
+    reroll_targets: bool,
+) -> Result<SyncSummary> {
+    sync_entries(repo, config, read_key_entries().await?, reroll_targets).await
+}
+
+/// Brings the database in line with the given keys, see [`sync`].
+async fn sync_entries(
+    repo: Arc<RepoImpls>,
+    config: Arc<Config>,
+    entries: Vec<KeyEntry>,
+    reroll_targets: bool,
+) -> Result<SyncSummary> {
+    let mut existing = accounts::search(repo.clone(), AccountConditions::default())
+        .await?
+        .into_iter()
+        .map(|account| (account.address.clone(), account))
+        .collect::<HashMap<_, _>>();
+
+    let mut summary = SyncSummary::default();
+    let mut listed = HashSet::new();
+
+    for entry in entries {
+        let address = entry.address.to_string();
+
+        if !listed.insert(address.clone()) {
+            tracing::warn!("Private key of `{address}` is listed more than once");
+            continue;
+        }
+
+        let Some(account) = existing.remove(&address) else {
+            match add_account(repo.clone(), &entry, &config).await {
+                Ok(()) => summary.added += 1,
+                Err(e) => {
+                    tracing::error!("Failed to add `{address}`: {e}");
+                    summary.failed += 1;
+                }
+            }
+            continue;
+        };
+
+        // goals of protocols registered since the last sync are added either way
+        let goals = account_goals::search_by_account_id(repo.clone(), account.id).await?;
+        account_goals::roll_targets(repo.clone(), account.id, &config, reroll_targets).await?;
+        let synced_goals = account_goals::search_by_account_id(repo.clone(), account.id).await?;
+
+        let mut synced = account.clone();
+        synced.proxy = entry.proxy;
+        synced.active = true;
+        synced.inactive_reason = None;
+        // an account that was given up on starts over with its retries
+        if !account.active {
+            synced.failure_count = 0;
+        }
+        if reroll_targets {
+            synced.bridge_goal |= !config.need_bridge;
+        }
+        synced.goal_reached = synced.is_finished(&synced_goals);
+
+        if synced == account && synced_goals == goals {
+            summary.unchanged += 1;
+            continue;
+        }
+
+        accounts::update(repo.clone(), AccountActiveModel::from(synced).reset_all()).await?;
+        summary.updated += 1;
+    }
+
+    for account in existing.into_values().filter(|account| account.active) {
+        tracing::warn!("Private key of `{}` is gone, marking it inactive", account.address);
+
+        let mut account = AccountActiveModel::from(account);
+        account.active = Set(false);
+        account.inactive_reason = Set(Some("private key removed".to_string()));
+        accounts::update(repo.clone(), account).await?;
+
+        summary.deactivated += 1;
+    }
+
+    Ok(summary)
+}
+
+/// Encrypts the private keys that are still stored in plain text and returns how many there
+/// were.
+///
+/// The keys that are encrypted already are decrypted to make sure the passphrase is the one they
+/// were encrypted with, so a mistyped passphrase fails here rather than in the middle of a run.
+pub async fn encrypt_private_keys(repo: Arc<RepoImpls>) -> Result<usize> {
+    let mut encrypted = 0;
+
+    for account in accounts::search(repo.clone(), AccountConditions::default()).await? {
+        if crypto::is_encrypted(&account.private_key) {
+            crypto::decrypt(&account.private_key)?;
+            continue;
+        }
+
+        let pk = crypto::encrypt(&account.private_key)?;
+        let mut account = AccountActiveModel::from(account);
+        account.private_key = Set(pk);
+        accounts::update(repo.clone(), account).await?;
+
+        encrypted += 1;
+    }
+
+    Ok(encrypted)
+}
+
+/// Whether any account has its private key encrypted already.
+pub async fn has_encrypted_keys(repo: Arc<RepoImpls>) -> Result<bool> {
+    let accounts = accounts::search(repo, AccountConditions::default()).await?;
+    Ok(accounts.iter().any(|account| crypto::is_encrypted(&account.private_key)))
+}
+
 pub async fn clear(repo: Arc<RepoImpls>) -> Result<()> {
     let _ = accounts::delete_all(repo.clone()).await?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::create_repositories_at;
+
+    fn entry(pk: &str) -> KeyEntry {
+        KeyEntry { pk: pk.to_string(), address: parse_private_key(pk).unwrap(), proxy: None }
+    }
+
+    #[tokio::test]
+    async fn sync_skips_keys_that_fail_to_be_added() {
+        let path =
+            std::env::temp_dir().join(format!("monad-pulse-{}-sync.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        crypto::unlock("test".to_string());
+
+        let repo = create_repositories_at(&path).await.unwrap();
+        let config = Arc::new(Config::parse(include_str!("../../data/config.toml")).unwrap());
+        let (first, second) = (
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d",
+        );
+
+        repo.account
+            .conn
+            .execute_unprepared(&format!(
+                "CREATE TRIGGER reject_second BEFORE INSERT ON account \
+                 WHEN NEW.address = '{}' BEGIN SELECT RAISE(ABORT, 'rejected'); END",
+                entry(second).address
+            ))
+            .await
+            .unwrap();
+
+        let summary =
+            sync_entries(repo.clone(), config.clone(), vec![entry(first), entry(second)], false)
+                .await
+                .unwrap();
+        assert_eq!((summary.added, summary.failed), (1, 1));
+
+        // the failed key is added by the next sync, while the first one is gone from the files
+        repo.account.conn.execute_unprepared("DROP TRIGGER reject_second").await.unwrap();
+        let summary = sync_entries(repo.clone(), config, vec![entry(second)], false).await.unwrap();
+        assert_eq!((summary.added, summary.deactivated, summary.failed), (1, 1, 0));
+
+        let accounts = accounts::search(repo, AccountConditions::default()).await.unwrap();
+        let active = accounts
+            .iter()
+            .map(|account| (account.address.clone(), account.active))
+            .collect::<HashMap<_, _>>();
+        assert!(!active[&entry(first).address.to_string()]);
+        assert!(active[&entry(second).address.to_string()]);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
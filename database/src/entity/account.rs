@@ -12,25 +12,40 @@ pub struct Model {
     #[sea_orm(unique)]
     pub private_key: String,
     pub proxy: Option<String>,
-    pub current_ambient_swaps_count: i32,
-    pub target_ambient_swaps_count: i32,
-    pub current_apriori_deposit_count: i32,
-    pub target_apriori_deposit_count: i32,
-    pub current_bean_swaps_count: i32,
-    pub target_bean_swaps_count: i32,
-    pub current_hashflow_swaps_count: i32,
-    pub target_hashflow_swaps_count: i32,
-    pub current_kinza_deposit_count: i32,
-    pub target_kinza_deposit_count: i32,
-    pub current_shmonad_deposit_count: i32,
-    pub target_shmonad_deposit_count: i32,
-    pub current_nad_domains_count: i32,
-    pub target_nad_domains_count: i32,
     pub bridge_goal: bool,
     pub goal_reached: bool,
+    pub active: bool,
+    pub resume_delay: Option<i32>,
+    pub failure_count: i32,
+    pub inactive_reason: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::account_goal::Entity")]
+    AccountGoal,
+    #[sea_orm(has_many = "super::balance_snapshot::Entity")]
+    BalanceSnapshot,
+    #[sea_orm(has_many = "super::transaction::Entity")]
+    Transaction,
+}
+
+impl Related<super::account_goal::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AccountGoal.def()
+    }
+}
+
+impl Related<super::balance_snapshot::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BalanceSnapshot.def()
+    }
+}
+
+impl Related<super::transaction::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Transaction.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
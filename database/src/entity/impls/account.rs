@@ -1,17 +1,18 @@
-use crate::Result;
+use crate::{Result, crypto};
 use alloy::{primitives::Address, signers::local::PrivateKeySigner};
 use common::state::{Dex, Lending, Nft};
 use derive_builder::Builder;
-use rand::seq::IndexedRandom;
 use sea_orm::Set;
 use std::str::FromStr;
 
-use super::prelude::{AccountActiveModel, AccountModel};
+use super::prelude::{AccountActiveModel, AccountGoalModel, AccountModel};
 
 #[derive(Debug, Default)]
 pub struct AccountConditions {
     pub goal_reached: Option<bool>,
     pub id: Option<i32>,
+    pub address: Option<String>,
+    pub active: Option<bool>,
 }
 
 #[derive(Builder)]
@@ -19,13 +20,6 @@ pub struct NewActiveModelOptions {
     pk: String,
     proxy: Option<String>,
     address: String,
-    target_ambient_swaps_count: u32,
-    target_apriori_deposit_count: u32,
-    target_bean_swaps_count: u32,
-    target_hashflow_swaps_count: u32,
-    target_kinza_deposit_count: u32,
-    target_shmonad_deposit_count: u32,
-    target_nad_domains_count: u32,
     bridge_goal: bool,
 }
 
@@ -35,13 +29,6 @@ impl AccountActiveModel {
             address: Set(opts.address),
             private_key: Set(opts.pk),
             proxy: Set(opts.proxy),
-            target_ambient_swaps_count: Set(opts.target_ambient_swaps_count as i32),
-            target_apriori_deposit_count: Set(opts.target_apriori_deposit_count as i32),
-            target_bean_swaps_count: Set(opts.target_bean_swaps_count as i32),
-            target_hashflow_swaps_count: Set(opts.target_hashflow_swaps_count as i32),
-            target_kinza_deposit_count: Set(opts.target_kinza_deposit_count as i32),
-            target_shmonad_deposit_count: Set(opts.target_shmonad_deposit_count as i32),
-            target_nad_domains_count: Set(opts.target_nad_domains_count as i32),
             bridge_goal: Set(opts.bridge_goal),
             ..Default::default()
         }
@@ -49,60 +36,27 @@ impl AccountActiveModel {
 }
 
 impl AccountModel {
-    fn available_actions(&self) -> Vec<AccountAction> {
-        let mut actions = vec![];
-
-        // swap protocols
-        if self.target_ambient_swaps_count > self.current_ambient_swaps_count {
-            actions.push(AccountAction::Swap(Dex::Ambient));
-        }
-
-        if self.target_bean_swaps_count > self.current_bean_swaps_count {
-            actions.push(AccountAction::Swap(Dex::Bean));
-        }
-
-        if self.target_hashflow_swaps_count > self.current_hashflow_swaps_count {
-            actions.push(AccountAction::Swap(Dex::Hashflow));
-        }
-
-        // lending protocols
-        if self.target_apriori_deposit_count > self.current_apriori_deposit_count {
-            actions.push(AccountAction::Lending(Lending::Apriori));
-        }
-
-        if self.target_kinza_deposit_count > self.current_kinza_deposit_count {
-            actions.push(AccountAction::Lending(Lending::Kinza));
-        }
-
-        if self.target_shmonad_deposit_count > self.current_shmonad_deposit_count {
-            actions.push(AccountAction::Lending(Lending::Shmonad));
-        }
-
-        // mint domains and nfts
-        if self.target_nad_domains_count > self.current_nad_domains_count {
-            actions.push(AccountAction::Mint(Nft::NadDomains));
-        }
-
-        actions
+    fn available_actions(goals: &[AccountGoalModel]) -> Vec<AccountAction> {
+        goals
+            .iter()
+            .filter(|goal| !goal.is_done())
+            .filter_map(|goal| goal.account_action())
+            .collect()
     }
 
-    pub fn random_available_action(&self) -> Option<AccountAction> {
-        // Checking if a bridge is needed
-        if !self.bridge_goal {
-            return Some(AccountAction::Bridge);
-        }
-
-        let actions = self.available_actions();
-
-        actions.choose(&mut rand::rng()).copied()
+    /// Whether the account has bridged and reached every goal.
+    pub fn is_finished(&self, goals: &[AccountGoalModel]) -> bool {
+        self.bridge_goal && Self::available_actions(goals).is_empty()
     }
 
-    pub fn signer(&self) -> PrivateKeySigner {
-        PrivateKeySigner::from_str(&self.private_key).unwrap() // private keys must be checked during db gen
+    /// Decrypts the private key, which needs [`crate::crypto::unlock`] to be called first.
+    pub fn signer(&self) -> Result<PrivateKeySigner> {
+        let pk = crypto::decrypt(&self.private_key)?;
+        Ok(PrivateKeySigner::from_str(&pk)?)
     }
 
     pub fn address(&self) -> Address {
-        self.signer().address()
+        self.address.parse().unwrap() // addresses are checked during db gen
     }
 
     fn proxy(&self) -> Option<reqwest::Proxy> {
@@ -125,6 +79,40 @@ impl AccountModel {
 pub enum AccountAction {
     Swap(Dex),
     Lending(Lending),
+    Withdraw(Lending),
     Mint(Nft),
     Bridge,
+    Wrap,
+    Unwrap,
+}
+
+impl AccountAction {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AccountAction::Swap(_) => "swap",
+            AccountAction::Lending(_) => "lending",
+            AccountAction::Withdraw(_) => "withdraw",
+            AccountAction::Mint(_) => "mint",
+            AccountAction::Bridge => "bridge",
+            AccountAction::Wrap => "wrap",
+            AccountAction::Unwrap => "unwrap",
+        }
+    }
+
+    /// Identifies the goal of the action in exported files, e.g. `swap_ambient`.
+    pub fn goal_key(&self) -> String {
+        format!("{}_{}", self.kind(), self.protocol()).to_lowercase().replace(' ', "_")
+    }
+
+    pub fn protocol(&self) -> String {
+        match self {
+            AccountAction::Swap(dex) => dex.to_string(),
+            AccountAction::Lending(lending) | AccountAction::Withdraw(lending) => {
+                lending.to_string()
+            }
+            AccountAction::Mint(nft) => nft.to_string(),
+            AccountAction::Bridge => "GASZIP".to_string(),
+            AccountAction::Wrap | AccountAction::Unwrap => "WMON".to_string(),
+        }
+    }
 }
@@ -0,0 +1,95 @@
+use common::{
+    config::Config,
+    state::{Dex, Lending, Nft},
+};
+use sea_orm::Set;
+
+use super::{
+    account::AccountAction,
+    prelude::{AccountGoalActiveModel, AccountGoalModel},
+};
+
+#[derive(Debug, Default)]
+pub struct AccountGoalConditions {
+    pub account_id: Option<i32>,
+}
+
+/// An action accounts have a goal for, along with the config range its target is drawn from.
+pub struct GoalSpec {
+    pub action: AccountAction,
+    pub target_range: fn(&Config) -> [u32; 2],
+}
+
+/// Every action with a per-account goal, in the order they're shown. Registering a protocol here
+/// is all it takes for new and synced accounts to get a goal for it.
+pub static GOALS: &[GoalSpec] = &[
+    GoalSpec {
+        action: AccountAction::Swap(Dex::Ambient),
+        target_range: |config| config.ambient_swap_count,
+    },
+    GoalSpec {
+        action: AccountAction::Swap(Dex::Bean),
+        target_range: |config| config.bean_swap_count,
+    },
+    GoalSpec {
+        action: AccountAction::Swap(Dex::Hashflow),
+        target_range: |config| config.hashflow_swap_count,
+    },
+    GoalSpec {
+        action: AccountAction::Lending(Lending::Apriori),
+        target_range: |config| config.apriori_deposit_count,
+    },
+    GoalSpec {
+        action: AccountAction::Lending(Lending::Kinza),
+        target_range: |config| config.kinza_deposit_count,
+    },
+    GoalSpec {
+        action: AccountAction::Lending(Lending::Shmonad),
+        target_range: |config| config.shmonad_deposit_count,
+    },
+    GoalSpec {
+        action: AccountAction::Withdraw(Lending::Apriori),
+        target_range: |config| config.apriori_withdraw_count,
+    },
+    GoalSpec {
+        action: AccountAction::Withdraw(Lending::Kinza),
+        target_range: |config| config.kinza_withdraw_count,
+    },
+    GoalSpec {
+        action: AccountAction::Withdraw(Lending::Shmonad),
+        target_range: |config| config.shmonad_withdraw_count,
+    },
+    GoalSpec { action: AccountAction::Wrap, target_range: |config| config.wmon_wrap_count },
+    GoalSpec { action: AccountAction::Unwrap, target_range: |config| config.wmon_unwrap_count },
+    GoalSpec {
+        action: AccountAction::Mint(Nft::NadDomains),
+        target_range: |config| config.nad_domains_count,
+    },
+];
+
+impl AccountGoalActiveModel {
+    /// A goal without progress. The current count is left to the database default.
+    pub fn new(account_id: i32, action: AccountAction, target: u32) -> Self {
+        Self {
+            account_id: Set(account_id),
+            action: Set(action.kind().to_string()),
+            protocol: Set(action.protocol()),
+            target: Set(target as i32),
+            ..Default::default()
+        }
+    }
+}
+
+impl AccountGoalModel {
+    /// The registered action the goal is for, `None` if its protocol isn't registered anymore.
+    pub fn account_action(&self) -> Option<AccountAction> {
+        GOALS
+            .iter()
+            .map(|goal| goal.action)
+            .find(|action| action.kind() == self.action && action.protocol() == self.protocol)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.current >= self.target
+    }
+}
@@ -0,0 +1,67 @@
+use alloy::primitives::{U256, utils::format_units};
+use common::onchain::token::Token;
+use sea_orm::{Set, prelude::DateTime};
+
+use super::prelude::{BalanceSnapshotActiveModel, BalanceSnapshotModel};
+
+#[derive(Debug, Default)]
+pub struct BalanceSnapshotConditions {
+    pub account_id: Option<i32>,
+    pub snapshot_id: Option<i32>,
+}
+
+/// A snapshot as a whole, numbered from 1 for the oldest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Snapshot {
+    pub id: i32,
+    pub taken_at: DateTime,
+}
+
+/// The balance of a token held by an account, as it's fetched for a snapshot.
+#[derive(Debug, Clone, Copy)]
+pub struct AccountBalance {
+    pub account_id: i32,
+    pub token: Token,
+    pub balance: U256,
+}
+
+impl BalanceSnapshotActiveModel {
+    pub fn new(balance: AccountBalance, snapshot: Snapshot) -> Self {
+        Self {
+            account_id: Set(balance.account_id),
+            chain_id: Set(balance.token.chain() as i64),
+            token: Set(balance.token.ticker().to_string()),
+            balance: Set(balance.balance.to_string()),
+            taken_at: Set(snapshot.taken_at),
+            snapshot_id: Set(snapshot.id),
+            ..Default::default()
+        }
+    }
+}
+
+impl BalanceSnapshotModel {
+    pub fn balance(&self) -> U256 {
+        self.balance.parse().unwrap_or_default()
+    }
+
+    /// The balance in whole tokens, or in the smallest unit if the token isn't registered, i.e.
+    /// it was removed from the registry or the registry couldn't be read.
+    pub fn amount(&self) -> String {
+        format_amount(&self.token, self.balance())
+    }
+}
+
+/// Formats an amount of the token with its decimals, without trailing zeros.
+pub fn format_amount(token: &str, amount: U256) -> String {
+    let Some(decimals) = Token::from_ticker(token).map(|token| token.decimals()) else {
+        return amount.to_string();
+    };
+
+    match format_units(amount, decimals) {
+        Ok(amount) if amount.contains('.') => {
+            amount.trim_end_matches('0').trim_end_matches('.').to_string()
+        }
+        Ok(amount) => amount,
+        Err(_) => amount.to_string(),
+    }
+}
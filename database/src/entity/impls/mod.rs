@@ -1,2 +1,5 @@
 pub mod account;
+pub mod account_goal;
+pub mod balance_snapshot;
 pub mod prelude;
+pub mod transaction;
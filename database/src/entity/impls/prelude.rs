@@ -1,3 +1,11 @@
-pub use crate::entity::account::{ActiveModel as AccountActiveModel, Model as AccountModel};
+pub use crate::entity::{
+    account::{ActiveModel as AccountActiveModel, Model as AccountModel},
+    account_goal::{ActiveModel as AccountGoalActiveModel, Model as AccountGoalModel},
+    balance_snapshot::{ActiveModel as BalanceSnapshotActiveModel, Model as BalanceSnapshotModel},
+    transaction::{ActiveModel as TransactionActiveModel, Model as TransactionModel},
+};
 
 pub type AccountList = Vec<AccountModel>;
+pub type AccountGoalList = Vec<AccountGoalModel>;
+pub type BalanceSnapshotList = Vec<BalanceSnapshotModel>;
+pub type TransactionList = Vec<TransactionModel>;
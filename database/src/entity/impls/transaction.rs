@@ -0,0 +1,118 @@
+use common::onchain::{action::PastTransaction, outcome::TxOutcome};
+use derive_builder::Builder;
+use sea_orm::Set;
+use std::fmt::Display;
+
+use super::prelude::{TransactionActiveModel, TransactionModel};
+
+#[derive(Debug, Default)]
+pub struct TransactionConditions {
+    pub account_id: Option<i32>,
+    pub status: Option<TransactionStatus>,
+    /// Only the transactions added after the one with this id.
+    pub after_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TransactionStatus {
+    Success,
+    Reverted,
+    /// The transaction got stuck and was replaced by a zero-value transfer to self.
+    Cancelled,
+    /// The action failed before or while sending the transaction.
+    Failed,
+}
+
+impl Display for TransactionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            TransactionStatus::Success => "success",
+            TransactionStatus::Reverted => "reverted",
+            TransactionStatus::Cancelled => "cancelled",
+            TransactionStatus::Failed => "failed",
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+/// Filled in step by step while an action runs, so that a record can be written even if the
+/// action fails halfway through.
+#[derive(Builder)]
+pub struct NewTransactionOptions {
+    account_id: i32,
+    chain_id: u64,
+    #[builder(setter(into))]
+    action: String,
+    #[builder(setter(into))]
+    protocol: String,
+    #[builder(default, setter(into, strip_option))]
+    token_in: Option<String>,
+    #[builder(default, setter(into, strip_option))]
+    token_out: Option<String>,
+    #[builder(default, setter(into, strip_option))]
+    amount_in: Option<String>,
+    #[builder(default, setter(into, strip_option))]
+    tx_hash: Option<String>,
+    status: TransactionStatus,
+    #[builder(default, setter(into, strip_option))]
+    gas_cost: Option<String>,
+    #[builder(default, setter(into, strip_option))]
+    error: Option<String>,
+    /// Number of transactions sent with the same nonce, including replacements.
+    #[builder(default = "1")]
+    attempts: u32,
+}
+
+impl NewTransactionOptionsBuilder {
+    pub fn outcome(&mut self, outcome: &TxOutcome) -> &mut Self {
+        let status = match (outcome.cancelled, outcome.is_success()) {
+            (true, _) => TransactionStatus::Cancelled,
+            (false, true) => TransactionStatus::Success,
+            (false, false) => TransactionStatus::Reverted,
+        };
+
+        self.gas_cost(outcome.gas_cost().to_string()).status(status).attempts(outcome.attempts);
+
+        if !outcome.simulated {
+            self.tx_hash(outcome.hash.to_string());
+        }
+
+        if let Some(reason) = &outcome.revert_reason {
+            self.error(reason.clone());
+        }
+
+        self
+    }
+}
+
+impl TransactionActiveModel {
+    pub fn new(opts: NewTransactionOptions) -> Self {
+        Self {
+            account_id: Set(opts.account_id),
+            chain_id: Set(opts.chain_id as i64),
+            action: Set(opts.action),
+            protocol: Set(opts.protocol),
+            token_in: Set(opts.token_in),
+            token_out: Set(opts.token_out),
+            amount_in: Set(opts.amount_in),
+            tx_hash: Set(opts.tx_hash),
+            status: Set(opts.status.to_string()),
+            gas_cost: Set(opts.gas_cost),
+            error: Set(opts.error),
+            attempts: Set(opts.attempts as i32),
+            ..Default::default()
+        }
+    }
+}
+
+impl TransactionModel {
+    /// The transaction as actions see it in the history, `None` if it was never sent.
+    pub fn past_transaction(&self) -> Option<PastTransaction> {
+        Some(PastTransaction {
+            action: self.action.clone(),
+            protocol: self.protocol.clone(),
+            hash: self.tx_hash.as_ref()?.parse().ok()?,
+        })
+    }
+}
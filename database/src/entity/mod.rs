@@ -1,5 +1,8 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.7
 
 pub mod account;
+pub mod account_goal;
+pub mod balance_snapshot;
 pub mod impls;
 pub mod prelude;
+pub mod transaction;
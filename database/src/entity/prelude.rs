@@ -1,3 +1,6 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.7
 
-pub use super::account::Entity as Account;
+pub use super::{
+    account::Entity as Account, account_goal::Entity as AccountGoal,
+    balance_snapshot::Entity as BalanceSnapshot, transaction::Entity as Transaction,
+};
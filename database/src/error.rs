@@ -1,8 +1,14 @@
 use alloy::signers::local::LocalSignerError;
-use sea_orm::DbErr;
+use common::error::ErrorClass;
+use sea_orm::{DbErr, RuntimeErr, sqlx};
 use thiserror::Error;
 
-use crate::entity::impls::account::NewActiveModelOptionsBuilderError;
+use crate::{
+    crypto::KeyError,
+    entity::impls::{
+        account::NewActiveModelOptionsBuilderError, transaction::NewTransactionOptionsBuilderError,
+    },
+};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -14,6 +20,9 @@ pub enum Error {
     #[error(transparent)]
     Request(#[from] reqwest::Error),
 
+    #[error(transparent)]
+    Key(#[from] KeyError),
+
     #[error(transparent)]
     Common(#[from] common::Error),
 
@@ -30,6 +39,73 @@ pub enum Error {
     #[error(transparent)]
     Toml(#[from] toml::de::Error),
 
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error("Column `{column}` has an invalid value `{value}`")]
+    InvalidCsvValue { column: String, value: String },
+
+    #[error("Column `{0}` is missing")]
+    MissingCsvColumn(String),
+
+    #[error("`{0}` is neither a .csv nor a .json file")]
+    UnsupportedFormat(String),
+
     #[error(transparent)]
     NewAccountOpts(#[from] NewActiveModelOptionsBuilderError),
+
+    #[error(transparent)]
+    NewTransactionOpts(#[from] NewTransactionOptionsBuilderError),
+}
+
+impl Error {
+    /// Decides how often an account tries again after failing with the error.
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            Error::Common(e) => e.class(),
+            Error::Db(e) => db_class(e),
+            Error::NotFound |
+            Error::Request(_) |
+            Error::Key(_) |
+            Error::LocalSigner(_) |
+            Error::Io(_) |
+            Error::Toml(_) |
+            Error::Csv(_) |
+            Error::Json(_) |
+            Error::InvalidCsvValue { .. } |
+            Error::MissingCsvColumn(_) |
+            Error::UnsupportedFormat(_) |
+            Error::NewAccountOpts(_) |
+            Error::NewTransactionOpts(_) => ErrorClass::Permanent,
+        }
+    }
+}
+
+/// Only a busy database or a failed connection is worth waiting for, an invalid query or a
+/// violated constraint fails the same way every time.
+fn db_class(e: &DbErr) -> ErrorClass {
+    let e = match e {
+        DbErr::ConnectionAcquire(_) => return ErrorClass::TransientDb,
+        DbErr::Conn(RuntimeErr::SqlxError(e)) |
+        DbErr::Exec(RuntimeErr::SqlxError(e)) |
+        DbErr::Query(RuntimeErr::SqlxError(e)) => e,
+        DbErr::Conn(RuntimeErr::Internal(_)) => return ErrorClass::TransientDb,
+        _ => return ErrorClass::Permanent,
+    };
+
+    match e {
+        sqlx::Error::Database(e) => match e.code().and_then(|code| code.parse::<i32>().ok()) {
+            // SQLITE_BUSY and SQLITE_LOCKED, the low byte of their extended codes as well
+            Some(code) if matches!(code & 0xff, 5 | 6) => ErrorClass::TransientDb,
+            _ => ErrorClass::Permanent,
+        },
+        sqlx::Error::Io(_) |
+        sqlx::Error::PoolTimedOut |
+        sqlx::Error::PoolClosed |
+        sqlx::Error::WorkerCrashed => ErrorClass::TransientDb,
+        _ => ErrorClass::Permanent,
+    }
 }
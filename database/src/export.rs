@@ -0,0 +1,436 @@
+use alloy::primitives::Address;
+use common::config::Config;
+use sea_orm::{ActiveValue::NotSet, Set};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+    sync::Arc,
+};
+
+use crate::{
+    crypto,
+    db::{check_proxy, parse_private_key},
+    entity::impls::{
+        account::AccountConditions,
+        account_goal::{AccountGoalConditions, GOALS},
+        prelude::{AccountActiveModel, AccountGoalActiveModel, AccountGoalModel, AccountModel},
+    },
+    error::{Error, Result},
+    repositories::RepoImpls,
+    use_cases::{account_goals, accounts, balance_snapshots},
+};
+
+/// The columns of a `.csv` file before the goal columns, which are named after the goal key with
+/// a `_current` or `_target` suffix.
+const CSV_COLUMNS: [&str; 6] =
+    ["address", "private_key", "proxy", "bridge_goal", "goal_reached", "active"];
+
+/// A row of the account table along with its goals as it's exported. Rows are matched by
+/// address, so the id is left out.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountRecord {
+    pub address: String,
+    #[serde(default)]
+    pub private_key: Option<String>,
+    #[serde(default)]
+    pub proxy: Option<String>,
+    pub bridge_goal: bool,
+    pub goal_reached: bool,
+    #[serde(default = "active_by_default")]
+    pub active: bool,
+    /// Keyed by [`crate::entity::impls::account::AccountAction::goal_key`].
+    #[serde(default)]
+    pub goals: BTreeMap<String, GoalRecord>,
+}
+
+/// A balance of a snapshot as it's exported, in the smallest unit of the token and in whole
+/// tokens.
+#[derive(Debug, Serialize)]
+pub struct BalanceRecord {
+    pub address: String,
+    pub chain_id: i64,
+    pub token: String,
+    pub balance: String,
+    pub amount: String,
+    pub taken_at: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct GoalRecord {
+    pub current: i32,
+    pub target: i32,
+}
+
+fn active_by_default() -> bool {
+    true
+}
+
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    pub added: usize,
+    pub updated: usize,
+    pub skipped: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum FileFormat {
+    Csv,
+    Json,
+}
+
+impl FileFormat {
+    fn from_path(path: &Path) -> Result<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("csv") => Ok(FileFormat::Csv),
+            Some("json") => Ok(FileFormat::Json),
+            _ => Err(Error::UnsupportedFormat(path.display().to_string())),
+        }
+    }
+}
+
+impl AccountRecord {
+    fn from_model(
+        account: AccountModel,
+        goals: &[AccountGoalModel],
+        include_private_key: bool,
+    ) -> Result<Self> {
+        let private_key =
+            if include_private_key { Some(crypto::decrypt(&account.private_key)?) } else { None };
+
+        let goals = goals
+            .iter()
+            .filter_map(|goal| {
+                let action = goal.account_action()?;
+                Some((action.goal_key(), GoalRecord { current: goal.current, target: goal.target }))
+            })
+            .collect();
+
+        Ok(Self {
+            address: account.address,
+            private_key,
+            proxy: account.proxy,
+            bridge_goal: account.bridge_goal,
+            goal_reached: account.goal_reached,
+            active: account.active,
+            goals,
+        })
+    }
+
+    fn csv_header() -> Vec<String> {
+        let mut header = CSV_COLUMNS.map(String::from).to_vec();
+
+        for goal in GOALS {
+            let key = goal.action.goal_key();
+            header.extend([format!("{key}_current"), format!("{key}_target")]);
+        }
+
+        header
+    }
+
+    fn to_csv_row(&self) -> Vec<String> {
+        let mut row = vec![
+            self.address.clone(),
+            self.private_key.clone().unwrap_or_default(),
+            self.proxy.clone().unwrap_or_default(),
+            self.bridge_goal.to_string(),
+            self.goal_reached.to_string(),
+            self.active.to_string(),
+        ];
+
+        for goal in GOALS {
+            let record = self.goals.get(&goal.action.goal_key()).copied().unwrap_or_default();
+            row.extend([record.current.to_string(), record.target.to_string()]);
+        }
+
+        row
+    }
+
+    fn from_csv_row(header: &csv::StringRecord, row: &csv::StringRecord) -> Result<Self> {
+        fn parse<T: std::str::FromStr>(column: &str, value: &str) -> Result<T> {
+            value.parse().map_err(|_| Error::InvalidCsvValue {
+                column: column.to_string(),
+                value: value.to_string(),
+            })
+        }
+
+        let mut record = AccountRecord {
+            address: String::new(),
+            private_key: None,
+            proxy: None,
+            bridge_goal: false,
+            goal_reached: false,
+            active: true,
+            goals: BTreeMap::new(),
+        };
+
+        for (column, value) in header.iter().zip(row.iter()) {
+            let optional = (!value.is_empty()).then(|| value.to_string());
+
+            match column {
+                "address" => record.address = value.to_string(),
+                "private_key" => record.private_key = optional,
+                "proxy" => record.proxy = optional,
+                "bridge_goal" => record.bridge_goal = parse(column, value)?,
+                "goal_reached" => record.goal_reached = parse(column, value)?,
+                "active" => record.active = parse(column, value)?,
+                _ => {
+                    if let Some(key) = column.strip_suffix("_current") {
+                        record.goals.entry(key.to_string()).or_default().current =
+                            parse(column, value)?;
+                    } else if let Some(key) = column.strip_suffix("_target") {
+                        record.goals.entry(key.to_string()).or_default().target =
+                            parse(column, value)?;
+                    }
+                }
+            }
+        }
+
+        Ok(record)
+    }
+
+    /// Checks the row the same way [`crate::db::generate`] does and returns the checksummed
+    /// address.
+    fn validate(&self) -> std::result::Result<String, String> {
+        let address = self
+            .address
+            .parse::<Address>()
+            .map_err(|e| format!("Address `{}` is invalid: {e}", self.address))?
+            .to_string();
+
+        if let Some(pk) = &self.private_key {
+            match parse_private_key(pk) {
+                Ok(pk_address) if pk_address.to_string() == address => {}
+                Ok(_) => {
+                    return Err(format!("Private key of `{address}` belongs to another address"))
+                }
+                Err(e) => return Err(format!("Private key of `{address}` is invalid: {e}")),
+            }
+        }
+
+        if let Some(proxy) = &self.proxy &&
+            let Err(e) = check_proxy(proxy)
+        {
+            return Err(format!("Proxy `{proxy}` of `{address}` is invalid: {e}"));
+        }
+
+        Ok(address)
+    }
+
+    fn to_active_model(&self, id: Option<i32>, address: String) -> Result<AccountActiveModel> {
+        let private_key = self.private_key.as_deref().map(crypto::encrypt).transpose()?;
+
+        Ok(AccountActiveModel {
+            id: id.map(Set).unwrap_or(NotSet),
+            address: Set(address),
+            private_key: private_key.map(Set).unwrap_or(NotSet),
+            proxy: Set(self.proxy.clone()),
+            bridge_goal: Set(self.bridge_goal),
+            goal_reached: Set(self.goal_reached),
+            active: Set(self.active),
+            resume_delay: NotSet,
+            failure_count: NotSet,
+            inactive_reason: match self.active {
+                true => Set(None),
+                false => NotSet,
+            },
+        })
+    }
+
+    /// The goals of the row whose protocol is registered. Any other goal is skipped.
+    fn goal_models(&self, account_id: i32) -> Vec<AccountGoalActiveModel> {
+        for key in self.goals.keys() {
+            if !GOALS.iter().any(|goal| goal.action.goal_key() == *key) {
+                tracing::warn!("Goal `{key}` of `{}` is unknown, skipping it", self.address);
+            }
+        }
+
+        GOALS
+            .iter()
+            .filter_map(|goal| {
+                let record = self.goals.get(&goal.action.goal_key())?;
+
+                let mut model =
+                    AccountGoalActiveModel::new(account_id, goal.action, record.target as u32);
+                model.current = Set(record.current);
+                Some(model)
+            })
+            .collect()
+    }
+}
+
+/// Writes every account to a `.csv` or `.json` file and returns the number of accounts.
+///
+/// Private keys are left empty unless `include_private_keys` is set, in which case they're
+/// written decrypted.
+pub async fn export(
+    repo: Arc<RepoImpls>,
+    path: impl AsRef<Path>,
+    include_private_keys: bool,
+) -> Result<usize> {
+    let path = path.as_ref();
+    let format = FileFormat::from_path(path)?;
+
+    let mut goals = HashMap::<i32, Vec<AccountGoalModel>>::new();
+    for goal in account_goals::search(repo.clone(), AccountGoalConditions::default()).await? {
+        goals.entry(goal.account_id).or_default().push(goal);
+    }
+
+    let records = accounts::search(repo, AccountConditions::default())
+        .await?
+        .into_iter()
+        .map(|account| {
+            let goals = goals.remove(&account.id).unwrap_or_default();
+            AccountRecord::from_model(account, &goals, include_private_keys)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let contents = match format {
+        FileFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(vec![]);
+            writer.write_record(AccountRecord::csv_header())?;
+            for record in &records {
+                writer.write_record(record.to_csv_row())?;
+            }
+            writer.into_inner().map_err(|e| e.into_error())?
+        }
+        FileFormat::Json => serde_json::to_vec_pretty(&records)?,
+    };
+
+    tokio::fs::write(path, contents).await?;
+
+    Ok(records.len())
+}
+
+/// Adds or updates the accounts in a `.csv` or `.json` file, matching them by address.
+///
+/// Every row is checked like in [`crate::db::generate`]. A row with an invalid address, private
+/// key or proxy is skipped, as is a new address without a private key. The private key of an
+/// existing account is kept if the row has none, otherwise it's encrypted before it's stored.
+/// Goals missing from the row are left as they are, except for a new account, which gets targets
+/// drawn from `config` for them like in [`crate::db::generate`].
+pub async fn import(
+    repo: Arc<RepoImpls>,
+    config: Arc<Config>,
+    path: impl AsRef<Path>,
+) -> Result<ImportSummary> {
+    let path = path.as_ref();
+    let format = FileFormat::from_path(path)?;
+    let contents = tokio::fs::read(path).await?;
+
+    let records: Vec<AccountRecord> = match format {
+        FileFormat::Csv => {
+            let mut reader = csv::Reader::from_reader(contents.as_slice());
+            let header = reader.headers()?.clone();
+
+            if !header.iter().any(|column| column == "address") {
+                return Err(Error::MissingCsvColumn("address".to_string()));
+            }
+
+            reader
+                .records()
+                .map(|row| AccountRecord::from_csv_row(&header, &row?))
+                .collect::<Result<_>>()?
+        }
+        FileFormat::Json => serde_json::from_slice(&contents)?,
+    };
+
+    let mut summary = ImportSummary::default();
+
+    for record in records {
+        let address = match record.validate() {
+            Ok(address) => address,
+            Err(e) => {
+                tracing::error!("{e}");
+                summary.skipped += 1;
+                continue;
+            }
+        };
+
+        let existing = accounts::search_account_by_address(repo.clone(), address.clone()).await?;
+
+        let (id, added) = match existing {
+            Some(account) => {
+                accounts::update(repo.clone(), record.to_active_model(Some(account.id), address)?)
+                    .await?;
+                summary.updated += 1;
+                (account.id, false)
+            }
+            None if record.private_key.is_none() => {
+                tracing::error!("Account `{address}` is new but has no private key");
+                summary.skipped += 1;
+                continue;
+            }
+            None => {
+                let id =
+                    accounts::add(repo.clone(), record.to_active_model(None, address)?).await?;
+                summary.added += 1;
+                (id, true)
+            }
+        };
+
+        account_goals::save(repo.clone(), record.goal_models(id)).await?;
+        // a new account without goals in the file would count as finished
+        if added {
+            account_goals::roll_targets(repo.clone(), id, &config, false).await?;
+        }
+
+        // an account with actions left is reopened even if it's marked as finished, so that
+        // raising its targets resumes it
+        let account = accounts::search_account_by_id(repo.clone(), id).await?;
+        let goals = account_goals::search_by_account_id(repo.clone(), id).await?;
+
+        if account.goal_reached && !account.is_finished(&goals) {
+            let mut account = AccountActiveModel::from(account);
+            account.goal_reached = Set(false);
+            accounts::update(repo.clone(), account).await?;
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Writes the balances of a snapshot to a `.csv` or `.json` file and returns the number of
+/// balances.
+pub async fn export_balances(
+    repo: Arc<RepoImpls>,
+    path: impl AsRef<Path>,
+    snapshot_id: i32,
+) -> Result<usize> {
+    let path = path.as_ref();
+    let format = FileFormat::from_path(path)?;
+
+    let addresses = accounts::search(repo.clone(), AccountConditions::default())
+        .await?
+        .into_iter()
+        .map(|account| (account.id, account.address))
+        .collect::<HashMap<_, _>>();
+
+    let records = balance_snapshots::of(repo, snapshot_id)
+        .await?
+        .into_iter()
+        .map(|balance| BalanceRecord {
+            address: addresses.get(&balance.account_id).cloned().unwrap_or_default(),
+            chain_id: balance.chain_id,
+            amount: balance.amount(),
+            token: balance.token,
+            balance: balance.balance,
+            taken_at: balance.taken_at.to_string(),
+        })
+        .collect::<Vec<_>>();
+
+    let contents = match format {
+        FileFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(vec![]);
+            for record in &records {
+                writer.serialize(record)?;
+            }
+            writer.into_inner().map_err(|e| e.into_error())?
+        }
+        FileFormat::Json => serde_json::to_vec_pretty(&records)?,
+    };
+
+    tokio::fs::write(path, contents).await?;
+
+    Ok(records.len())
+}
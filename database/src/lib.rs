@@ -1,6 +1,8 @@
+pub mod crypto;
 pub mod db;
 pub mod entity;
 pub mod error;
+pub mod export;
 pub mod repositories;
 pub mod use_cases;
 
@@ -31,6 +31,18 @@ impl AccountRepo for AccountRepoImpl {
             conditions = conditions.add(entity::account::Column::GoalReached.eq(goal_reached));
         }
 
+        if let Some(id) = filter.id {
+            conditions = conditions.add(entity::account::Column::Id.eq(id));
+        }
+
+        if let Some(address) = filter.address {
+            conditions = conditions.add(entity::account::Column::Address.eq(address));
+        }
+
+        if let Some(active) = filter.active {
+            conditions = conditions.add(entity::account::Column::Active.eq(active));
+        }
+
         query = query.filter(conditions);
         let accounts = query.all(&self.conn).await?;
         Ok(accounts)
@@ -0,0 +1,74 @@
+use crate::{
+    entity,
+    entity::{
+        impls::{account_goal::AccountGoalConditions, prelude::*},
+        prelude::AccountGoal,
+    },
+    error::Result,
+};
+use async_trait::async_trait;
+use entity::account_goal::Column;
+use sea_orm::{
+    ColumnTrait, Condition, DbConn, EntityTrait, QueryFilter, QueryOrder, sea_query::OnConflict,
+};
+
+pub struct AccountGoalRepoImpl {
+    pub conn: DbConn,
+}
+
+#[async_trait]
+pub trait AccountGoalRepo {
+    async fn find_all(&self, filter: AccountGoalConditions) -> Result<AccountGoalList>;
+    /// Inserts the goals, updating `columns` of the ones that exist already. With no columns an
+    /// existing goal is left as is.
+    async fn upsert(&self, goals: Vec<AccountGoalActiveModel>, columns: &[Column]) -> Result<()>;
+    async fn increment(&self, account_id: i32, action: &str, protocol: &str) -> Result<u64>;
+}
+
+#[async_trait]
+impl AccountGoalRepo for AccountGoalRepoImpl {
+    async fn find_all(&self, filter: AccountGoalConditions) -> Result<AccountGoalList> {
+        let mut query = AccountGoal::find();
+        let mut conditions = Condition::all();
+
+        if let Some(account_id) = filter.account_id {
+            conditions = conditions.add(Column::AccountId.eq(account_id));
+        }
+
+        query = query.filter(conditions).order_by_asc(Column::Id);
+        let goals = query.all(&self.conn).await?;
+        Ok(goals)
+    }
+
+    async fn upsert(&self, goals: Vec<AccountGoalActiveModel>, columns: &[Column]) -> Result<()> {
+        if goals.is_empty() {
+            return Ok(());
+        }
+
+        let mut on_conflict =
+            OnConflict::columns([Column::AccountId, Column::Action, Column::Protocol]);
+        match columns {
+            [] => on_conflict.do_nothing(),
+            columns => on_conflict.update_columns(columns.iter().copied()),
+        };
+
+        AccountGoal::insert_many(goals)
+            .on_conflict(on_conflict.to_owned())
+            .exec_without_returning(&self.conn)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn increment(&self, account_id: i32, action: &str, protocol: &str) -> Result<u64> {
+        let result = AccountGoal::update_many()
+            .col_expr(Column::Current, Column::Current.into_expr().add(1))
+            .filter(Column::AccountId.eq(account_id))
+            .filter(Column::Action.eq(action))
+            .filter(Column::Protocol.eq(protocol))
+            .exec(&self.conn)
+            .await?;
+
+        Ok(result.rows_affected)
+    }
+}
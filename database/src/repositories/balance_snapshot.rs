@@ -0,0 +1,71 @@
+use crate::{
+    entity,
+    entity::{
+        impls::{balance_snapshot::BalanceSnapshotConditions, prelude::*},
+        prelude::BalanceSnapshot,
+    },
+    error::Result,
+};
+use async_trait::async_trait;
+use entity::balance_snapshot::Column;
+use sea_orm::{
+    ColumnTrait, Condition, DbConn, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+    prelude::DateTime,
+};
+
+pub struct BalanceSnapshotRepoImpl {
+    pub conn: DbConn,
+}
+
+#[async_trait]
+pub trait BalanceSnapshotRepo {
+    async fn find_all(&self, filter: BalanceSnapshotConditions) -> Result<BalanceSnapshotList>;
+    async fn add_many(&self, balances: Vec<BalanceSnapshotActiveModel>) -> Result<()>;
+    /// The id and time of every snapshot, oldest first.
+    async fn find_snapshots(&self) -> Result<Vec<(i32, DateTime)>>;
+}
+
+#[async_trait]
+impl BalanceSnapshotRepo for BalanceSnapshotRepoImpl {
+    async fn find_all(&self, filter: BalanceSnapshotConditions) -> Result<BalanceSnapshotList> {
+        let mut query = BalanceSnapshot::find();
+        let mut conditions = Condition::all();
+
+        if let Some(account_id) = filter.account_id {
+            conditions = conditions.add(Column::AccountId.eq(account_id));
+        }
+
+        if let Some(snapshot_id) = filter.snapshot_id {
+            conditions = conditions.add(Column::SnapshotId.eq(snapshot_id));
+        }
+
+        query = query.filter(conditions).order_by_asc(Column::Id);
+        let balances = query.all(&self.conn).await?;
+        Ok(balances)
+    }
+
+    async fn add_many(&self, balances: Vec<BalanceSnapshotActiveModel>) -> Result<()> {
+        if balances.is_empty() {
+            return Ok(());
+        }
+
+        BalanceSnapshot::insert_many(balances).exec_without_returning(&self.conn).await?;
+
+        Ok(())
+    }
+
+    async fn find_snapshots(&self) -> Result<Vec<(i32, DateTime)>> {
+        // every balance of a snapshot has the same time
+        let snapshots = BalanceSnapshot::find()
+            .select_only()
+            .column(Column::SnapshotId)
+            .column(Column::TakenAt)
+            .distinct()
+            .order_by_asc(Column::SnapshotId)
+            .into_tuple()
+            .all(&self.conn)
+            .await?;
+
+        Ok(snapshots)
+    }
+}
@@ -1,32 +1,95 @@
-use crate::{db::connect, error::Result};
+use crate::{
+    db::{connect, connect_at, connect_scratch},
+    error::Result,
+};
 use account::{AccountRepo, AccountRepoImpl};
-use std::sync::Arc;
+use account_goal::{AccountGoalRepo, AccountGoalRepoImpl};
+use balance_snapshot::{BalanceSnapshotRepo, BalanceSnapshotRepoImpl};
+use sea_orm::DbConn;
+use std::{path::Path, sync::Arc};
+use transaction::{TransactionRepo, TransactionRepoImpl};
 
 pub mod account;
+pub mod account_goal;
+pub mod balance_snapshot;
+pub mod transaction;
 
 pub struct RepoImpls {
     pub account: AccountRepoImpl,
+    pub account_goal: AccountGoalRepoImpl,
+    pub balance_snapshot: BalanceSnapshotRepoImpl,
+    pub transaction: TransactionRepoImpl,
 }
 
 pub trait Repositories {
     type AccountRepoImpl: AccountRepo;
+    type AccountGoalRepoImpl: AccountGoalRepo;
+    type BalanceSnapshotRepoImpl: BalanceSnapshotRepo;
+    type TransactionRepoImpl: TransactionRepo;
 
     fn account(&self) -> &Self::AccountRepoImpl;
+    fn account_goal(&self) -> &Self::AccountGoalRepoImpl;
+    fn balance_snapshot(&self) -> &Self::BalanceSnapshotRepoImpl;
+    fn transaction(&self) -> &Self::TransactionRepoImpl;
 }
 
 impl Repositories for RepoImpls {
     type AccountRepoImpl = AccountRepoImpl;
+    type AccountGoalRepoImpl = AccountGoalRepoImpl;
+    type BalanceSnapshotRepoImpl = BalanceSnapshotRepoImpl;
+    type TransactionRepoImpl = TransactionRepoImpl;
 
     fn account(&self) -> &Self::AccountRepoImpl {
         &self.account
     }
+
+    fn account_goal(&self) -> &Self::AccountGoalRepoImpl {
+        &self.account_goal
+    }
+
+    fn balance_snapshot(&self) -> &Self::BalanceSnapshotRepoImpl {
+        &self.balance_snapshot
+    }
+
+    fn transaction(&self) -> &Self::TransactionRepoImpl {
+        &self.transaction
+    }
+}
+
+impl RepoImpls {
+    fn new(conn: DbConn) -> Self {
+        let account_repo = AccountRepoImpl { conn: conn.clone() };
+        let account_goal_repo = AccountGoalRepoImpl { conn: conn.clone() };
+        let balance_snapshot_repo = BalanceSnapshotRepoImpl { conn: conn.clone() };
+        let transaction_repo = TransactionRepoImpl { conn };
+
+        Self {
+            account: account_repo,
+            account_goal: account_goal_repo,
+            balance_snapshot: balance_snapshot_repo,
+            transaction: transaction_repo,
+        }
+    }
 }
 
 pub async fn create_repositories() -> Result<Arc<RepoImpls>> {
     let conn = connect().await?;
-    let account_repo = AccountRepoImpl { conn };
 
-    let repos = RepoImpls { account: account_repo };
+    Ok(Arc::new(RepoImpls::new(conn)))
+}
+
+/// Creates repositories backed by a copy of the database, so that a dry run can make progress
+/// without touching the real one.
+pub async fn create_scratch_repositories(repo: &RepoImpls) -> Result<Arc<RepoImpls>> {
+    let conn = connect_scratch(&repo.account.conn).await?;
+
+    Ok(Arc::new(RepoImpls::new(conn)))
+}
+
+/// Creates repositories backed by the database file at `path`, which is created and migrated if
+/// needed.
+pub async fn create_repositories_at(path: impl AsRef<Path>) -> Result<Arc<RepoImpls>> {
+    let conn = connect_at(path.as_ref()).await?;
 
-    Ok(Arc::new(repos))
+    Ok(Arc::new(RepoImpls::new(conn)))
 }
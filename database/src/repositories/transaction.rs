@@ -0,0 +1,60 @@
+use crate::{
+    entity,
+    entity::{
+        impls::{prelude::*, transaction::TransactionConditions},
+        prelude::Transaction,
+    },
+    error::Result,
+};
+use async_trait::async_trait;
+use sea_orm::{ColumnTrait, Condition, DbConn, EntityTrait, QueryFilter, QueryOrder};
+
+pub struct TransactionRepoImpl {
+    pub conn: DbConn,
+}
+
+#[async_trait]
+pub trait TransactionRepo {
+    async fn find_all(&self, filter: TransactionConditions) -> Result<TransactionList>;
+    async fn add(&self, transaction: TransactionActiveModel) -> Result<i32>;
+    async fn last_id(&self) -> Result<i32>;
+}
+
+#[async_trait]
+impl TransactionRepo for TransactionRepoImpl {
+    async fn find_all(&self, filter: TransactionConditions) -> Result<TransactionList> {
+        let mut query = Transaction::find();
+        let mut conditions = Condition::all();
+
+        if let Some(account_id) = filter.account_id {
+            conditions = conditions.add(entity::transaction::Column::AccountId.eq(account_id));
+        }
+
+        if let Some(status) = filter.status {
+            conditions = conditions.add(entity::transaction::Column::Status.eq(status.to_string()));
+        }
+
+        if let Some(after_id) = filter.after_id {
+            conditions = conditions.add(entity::transaction::Column::Id.gt(after_id));
+        }
+
+        query = query.filter(conditions).order_by_asc(entity::transaction::Column::Id);
+        let transactions = query.all(&self.conn).await?;
+        Ok(transactions)
+    }
+
+    async fn add(&self, transaction: TransactionActiveModel) -> Result<i32> {
+        let id = Transaction::insert(transaction).exec(&self.conn).await?.last_insert_id;
+
+        Ok(id)
+    }
+
+    async fn last_id(&self) -> Result<i32> {
+        let last = Transaction::find()
+            .order_by_desc(entity::transaction::Column::Id)
+            .one(&self.conn)
+            .await?;
+
+        Ok(last.map_or(0, |transaction| transaction.id))
+    }
+}
@@ -0,0 +1,66 @@
+use common::{config::Config, utils::random::random_in_range};
+
+use crate::{
+    entity::{
+        account_goal::Column,
+        impls::{
+            account_goal::{AccountGoalConditions, GOALS},
+            prelude::*,
+        },
+    },
+    error::Result,
+    repositories::{Repositories, account_goal::AccountGoalRepo},
+};
+use std::sync::Arc;
+
+pub async fn search<R: Repositories>(
+    repo: Arc<R>,
+    filter: AccountGoalConditions,
+) -> Result<AccountGoalList> {
+    repo.account_goal().find_all(filter).await
+}
+
+pub async fn search_by_account_id<R: Repositories>(
+    repo: Arc<R>,
+    account_id: i32,
+) -> Result<AccountGoalList> {
+    search(repo, AccountGoalConditions { account_id: Some(account_id) }).await
+}
+
+/// Draws a target from the config for every registered goal of the account. The progress is
+/// kept, and the targets of existing goals are only replaced with `overwrite`, so without it
+/// only goals of newly registered protocols are added.
+pub async fn roll_targets<R: Repositories>(
+    repo: Arc<R>,
+    account_id: i32,
+    config: &Config,
+    overwrite: bool,
+) -> Result<()> {
+    let goals = GOALS
+        .iter()
+        .map(|goal| {
+            AccountGoalActiveModel::new(
+                account_id,
+                goal.action,
+                random_in_range((goal.target_range)(config)),
+            )
+        })
+        .collect();
+
+    let columns: &[Column] = if overwrite { &[Column::Target] } else { &[] };
+    repo.account_goal().upsert(goals, columns).await
+}
+
+/// Writes the goals as they are, replacing both counts of existing ones.
+pub async fn save<R: Repositories>(repo: Arc<R>, goals: Vec<AccountGoalActiveModel>) -> Result<()> {
+    repo.account_goal().upsert(goals, &[Column::Current, Column::Target]).await
+}
+
+pub async fn increment<R: Repositories>(
+    repo: Arc<R>,
+    account_id: i32,
+    action: &str,
+    protocol: &str,
+) -> Result<u64> {
+    repo.account_goal().increment(account_id, action, protocol).await
+}
@@ -1,4 +1,3 @@
-use common::state::{Dex, Lending, Nft};
 use sea_orm::{
     ActiveValue::{self, Set},
     DbErr,
@@ -23,10 +22,21 @@ pub async fn search<R: Repositories>(
 
 pub async fn search_account_by_id<R: Repositories>(repo: Arc<R>, id: i32) -> Result<AccountModel> {
     let accounts =
-        repo.account().find_all(AccountConditions { id: Some(id), goal_reached: None }).await?;
+        repo.account().find_all(AccountConditions { id: Some(id), ..Default::default() }).await?;
     accounts.into_iter().next().ok_or(Error::Db(DbErr::RecordNotFound(format!("{id}"))))
 }
 
+pub async fn search_account_by_address<R: Repositories>(
+    repo: Arc<R>,
+    address: String,
+) -> Result<Option<AccountModel>> {
+    let accounts = repo
+        .account()
+        .find_all(AccountConditions { address: Some(address), ..Default::default() })
+        .await?;
+    Ok(accounts.into_iter().next())
+}
+
 pub async fn add<R: Repositories>(repo: Arc<R>, new_account: ActiveModel) -> Result<i32> {
     repo.account().add(new_account).await
 }
@@ -50,70 +60,47 @@ pub async fn deactivate_account_by_id<R: Repositories>(repo: Arc<R>, id: i32) ->
     repo.account().update(active_model).await
 }
 
-pub async fn update_swap_count<R: Repositories>(
-    repo: Arc<R>,
-    dex: Dex,
-    account: &AccountModel,
-) -> Result<i32> {
-    let mut active_model =
-        AccountActiveModel { id: ActiveValue::set(account.id), ..Default::default() };
+/// Takes the account out of the warmup without counting it as finished, e.g. after too many
+/// failures in a row. The reason is shown by `status`.
+pub async fn give_up<R: Repositories>(repo: Arc<R>, id: i32, reason: String) -> Result<i32> {
+    let mut active_model = AccountActiveModel { id: ActiveValue::set(id), ..Default::default() };
+
+    active_model.active = Set(false);
+    active_model.inactive_reason = Set(Some(reason));
 
-    match dex {
-        Dex::Ambient => {
-            active_model.current_ambient_swaps_count =
-                ActiveValue::set(account.current_ambient_swaps_count + 1);
-        }
-        Dex::Bean => {
-            active_model.current_bean_swaps_count =
-                ActiveValue::set(account.current_bean_swaps_count + 1)
-        }
-        Dex::Hashflow => {
-            active_model.current_hashflow_swaps_count =
-                ActiveValue::set(account.current_hashflow_swaps_count + 1)
-        }
-    }
-
-    update(repo, active_model).await
+    repo.account().update(active_model).await
 }
 
-pub async fn update_deposit_count<R: Repositories>(
+/// Saves the seconds the account had left to wait when the warmup stopped, or clears them with
+/// `None` once they're waited out.
+pub async fn set_resume_delay<R: Repositories>(
     repo: Arc<R>,
-    lending: Lending,
-    account: &AccountModel,
+    id: i32,
+    resume_delay: Option<i32>,
 ) -> Result<i32> {
-    let mut active_model =
-        AccountActiveModel { id: ActiveValue::set(account.id), ..Default::default() };
+    let mut active_model = AccountActiveModel { id: ActiveValue::set(id), ..Default::default() };
 
-    match lending {
-        Lending::Apriori => {
-            active_model.current_apriori_deposit_count =
-                Set(account.current_apriori_deposit_count + 1)
-        }
-        Lending::Kinza => {
-            active_model.current_kinza_deposit_count = Set(account.current_kinza_deposit_count + 1)
-        }
-        Lending::Shmonad => {
-            active_model.current_shmonad_deposit_count =
-                Set(account.current_shmonad_deposit_count + 1)
-        }
-    };
+    active_model.resume_delay = Set(resume_delay);
 
     repo.account().update(active_model).await
 }
 
-pub async fn update_mint_count<R: Repositories>(
-    repo: Arc<R>,
-    nft: Nft,
-    account: AccountModel,
-) -> Result<i32> {
-    let mut active_model =
-        AccountActiveModel { id: ActiveValue::set(account.id), ..Default::default() };
+/// Counts one more failure in a row of the account and returns how many there are now.
+pub async fn record_failure<R: Repositories>(repo: Arc<R>, id: i32) -> Result<i32> {
+    let account = search_account_by_id(repo.clone(), id).await?;
+    let failure_count = account.failure_count + 1;
+
+    let mut active_model = AccountActiveModel::from(account);
+    active_model.failure_count = Set(failure_count);
+    repo.account().update(active_model).await?;
+
+    Ok(failure_count)
+}
+
+pub async fn reset_failures<R: Repositories>(repo: Arc<R>, id: i32) -> Result<i32> {
+    let mut active_model = AccountActiveModel { id: ActiveValue::set(id), ..Default::default() };
 
-    match nft {
-        Nft::NadDomains => {
-            active_model.current_nad_domains_count = Set(account.current_nad_domains_count + 1)
-        }
-    };
+    active_model.failure_count = Set(0);
 
     repo.account().update(active_model).await
 }
@@ -0,0 +1,49 @@
+use crate::{
+    entity::impls::{
+        balance_snapshot::{AccountBalance, BalanceSnapshotConditions, Snapshot},
+        prelude::*,
+    },
+    error::Result,
+    repositories::{Repositories, balance_snapshot::BalanceSnapshotRepo},
+};
+use chrono::{SubsecRound, Utc};
+use std::sync::Arc;
+
+pub async fn search<R: Repositories>(
+    repo: Arc<R>,
+    filter: BalanceSnapshotConditions,
+) -> Result<BalanceSnapshotList> {
+    repo.balance_snapshot().find_all(filter).await
+}
+
+/// Stores the balances as a new snapshot, numbered after the latest one, and returns it.
+pub async fn add<R: Repositories>(repo: Arc<R>, balances: Vec<AccountBalance>) -> Result<Snapshot> {
+    let last_id = list(repo.clone()).await?.last().map_or(0, |snapshot| snapshot.id);
+    let snapshot = Snapshot { id: last_id + 1, taken_at: Utc::now().naive_utc().trunc_subsecs(0) };
+
+    let balances = balances
+        .into_iter()
+        .map(|balance| BalanceSnapshotActiveModel::new(balance, snapshot))
+        .collect();
+
+    repo.balance_snapshot().add_many(balances).await?;
+
+    Ok(snapshot)
+}
+
+/// Every snapshot, oldest first.
+pub async fn list<R: Repositories>(repo: Arc<R>) -> Result<Vec<Snapshot>> {
+    Ok(repo
+        .balance_snapshot()
+        .find_snapshots()
+        .await?
+        .into_iter()
+        .map(|(id, taken_at)| Snapshot { id, taken_at })
+        .collect())
+}
+
+/// The balances of a snapshot.
+pub async fn of<R: Repositories>(repo: Arc<R>, snapshot_id: i32) -> Result<BalanceSnapshotList> {
+    search(repo, BalanceSnapshotConditions { snapshot_id: Some(snapshot_id), ..Default::default() })
+        .await
+}
@@ -1 +1,4 @@
+pub mod account_goals;
 pub mod accounts;
+pub mod balance_snapshots;
+pub mod transactions;
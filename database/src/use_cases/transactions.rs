@@ -0,0 +1,49 @@
+use crate::{
+    entity::impls::{
+        prelude::*,
+        transaction::{TransactionConditions, TransactionStatus},
+    },
+    error::Result,
+    repositories::{Repositories, transaction::TransactionRepo},
+};
+use common::onchain::action::PastTransaction;
+use std::sync::Arc;
+
+pub async fn search<R: Repositories>(
+    repo: Arc<R>,
+    filter: TransactionConditions,
+) -> Result<TransactionList> {
+    repo.transaction().find_all(filter).await
+}
+
+pub async fn add<R: Repositories>(
+    repo: Arc<R>,
+    transaction: TransactionActiveModel,
+) -> Result<i32> {
+    repo.transaction().add(transaction).await
+}
+
+/// The id of the latest transaction, 0 if there's none yet.
+pub async fn last_id<R: Repositories>(repo: Arc<R>) -> Result<i32> {
+    repo.transaction().last_id().await
+}
+
+/// The confirmed transactions of an account, oldest first, as the actions get them.
+pub async fn history<R: Repositories>(
+    repo: Arc<R>,
+    account_id: i32,
+) -> Result<Vec<PastTransaction>> {
+    let filter = TransactionConditions {
+        account_id: Some(account_id),
+        status: Some(TransactionStatus::Success),
+        ..Default::default()
+    };
+
+    Ok(repo
+        .transaction()
+        .find_all(filter)
+        .await?
+        .iter()
+        .filter_map(|transaction| transaction.past_transaction())
+        .collect())
+}
@@ -0,0 +1,68 @@
+use common::error::ErrorClass;
+use database::Error;
+use sea_orm::{
+    ConnAcquireErr, ConnectOptions, ConnectionTrait, Database, DbConn, DbErr, TransactionTrait,
+};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// A database file of its own for the test, removed first in case a previous run left it.
+fn database_path(name: &str) -> PathBuf {
+    let path =
+        std::env::temp_dir().join(format!("monad-pulse-{name}-{}.sqlite", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+/// Connects without waiting for a lock, so that a locked database fails right away.
+async fn connect(path: &Path) -> DbConn {
+    let mut options = ConnectOptions::new(format!("sqlite://{}?mode=rwc", path.display()));
+    options.sqlx_logging(false).map_sqlx_sqlite_opts(|opts| opts.busy_timeout(Duration::ZERO));
+
+    Database::connect(options).await.unwrap()
+}
+
+fn class_of(e: DbErr) -> ErrorClass {
+    Error::from(e).class()
+}
+
+#[tokio::test]
+async fn classifies_locked_database_as_transient() {
+    let path = database_path("locked");
+    let (writer, other) = (connect(&path).await, connect(&path).await);
+    writer.execute_unprepared("CREATE TABLE account (id INTEGER PRIMARY KEY)").await.unwrap();
+
+    // the open transaction holds the write lock
+    let txn = writer.begin().await.unwrap();
+    txn.execute_unprepared("INSERT INTO account (id) VALUES (1)").await.unwrap();
+
+    let e = other.execute_unprepared("INSERT INTO account (id) VALUES (2)").await.unwrap_err();
+    assert_eq!(class_of(e), ErrorClass::TransientDb);
+
+    txn.rollback().await.unwrap();
+    std::fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn classifies_constraint_and_schema_errors_as_permanent() {
+    let path = database_path("constraint");
+    let conn = connect(&path).await;
+    conn.execute_unprepared("CREATE TABLE account (id INTEGER PRIMARY KEY)").await.unwrap();
+    conn.execute_unprepared("INSERT INTO account (id) VALUES (1)").await.unwrap();
+
+    let e = conn.execute_unprepared("INSERT INTO account (id) VALUES (1)").await.unwrap_err();
+    assert_eq!(class_of(e), ErrorClass::Permanent);
+
+    let e = conn.execute_unprepared("SELECT id FROM wallet").await.unwrap_err();
+    assert_eq!(class_of(e), ErrorClass::Permanent);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn classifies_unavailable_connection_as_transient() {
+    let e = DbErr::ConnectionAcquire(ConnAcquireErr::Timeout);
+    assert_eq!(class_of(e), ErrorClass::TransientDb);
+}
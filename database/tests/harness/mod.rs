@@ -0,0 +1,62 @@
+#![allow(dead_code)]
+
+use common::config::Config;
+use database::{
+    crypto,
+    repositories::{RepoImpls, create_repositories_at},
+};
+use std::{path::PathBuf, sync::Arc};
+
+/// Anvil's first two dev accounts, as private key and address.
+pub const ACCOUNTS: [(&str, &str); 2] = [
+    (
+        "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+        "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266",
+    ),
+    (
+        "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d",
+        "0x70997970C51812dc3A010C7d01b50e0d17dc79C8",
+    ),
+];
+
+/// A file in the temp dir of its own for the test, removed first in case a previous run left it
+/// and again when it's dropped.
+pub struct TempFile(pub PathBuf);
+
+impl TempFile {
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("monad-pulse-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        Self(path)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// A migrated database of its own for the test, with the private key encryption unlocked.
+pub struct TestDb {
+    pub repo: Arc<RepoImpls>,
+    _file: TempFile,
+}
+
+impl TestDb {
+    pub async fn new(name: &str) -> Self {
+        crypto::unlock("test".to_string());
+
+        let file = TempFile::new(&format!("{name}.sqlite"));
+        let repo = create_repositories_at(&file.0).await.unwrap();
+
+        Self { repo, _file: file }
+    }
+}
+
+/// The shipped config with a fixed target of two Ambient swaps.
+pub fn config() -> Arc<Config> {
+    let mut config = Config::parse(include_str!("../../../data/config.toml")).unwrap();
+    config.ambient_swap_count = [2, 2];
+    Arc::new(config)
+}
@@ -0,0 +1,76 @@
+mod harness;
+
+use database::{repositories::create_repositories_at, use_cases::account_goals};
+use harness::TempFile;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{ConnectionTrait, Database, DbBackend, Statement};
+
+/// The migrations up to the one that moves the goals into the account_goal table.
+const BEFORE_ACCOUNT_GOAL: u32 = 4;
+
+#[tokio::test]
+async fn moves_the_goal_columns_into_account_goal() {
+    let file = TempFile::new("account-goal-migration.sqlite");
+    let db = Database::connect(format!("sqlite://{}?mode=rwc", file.0.display())).await.unwrap();
+
+    Migrator::up(&db, Some(BEFORE_ACCOUNT_GOAL)).await.unwrap();
+    db.execute_unprepared(
+        "INSERT INTO account (address, private_key, bridge_goal, goal_reached, active, \
+         current_ambient_swaps_count, target_ambient_swaps_count, \
+         current_bean_swaps_count, target_bean_swaps_count, \
+         current_hashflow_swaps_count, target_hashflow_swaps_count, \
+         current_apriori_deposit_count, target_apriori_deposit_count, \
+         current_kinza_deposit_count, target_kinza_deposit_count, \
+         current_shmonad_deposit_count, target_shmonad_deposit_count, \
+         current_nad_domains_count, target_nad_domains_count) \
+         VALUES ('0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266', 'pk', true, false, true, \
+         1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14)",
+    )
+    .await
+    .unwrap();
+
+    Migrator::up(&db, None).await.unwrap();
+
+    let rows = db
+        .query_all(Statement::from_string(
+            DbBackend::Sqlite,
+            "SELECT action, protocol, current, target FROM account_goal ORDER BY id",
+        ))
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|row| {
+            (
+                row.try_get::<String>("", "action").unwrap(),
+                row.try_get::<String>("", "protocol").unwrap(),
+                row.try_get::<i32>("", "current").unwrap(),
+                row.try_get::<i32>("", "target").unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let expected = [
+        ("swap", "AMBIENT", 1, 2),
+        ("swap", "BEAN", 3, 4),
+        ("swap", "HASHFLOW", 5, 6),
+        ("lending", "APRIORI", 7, 8),
+        ("lending", "KINZA", 9, 10),
+        ("lending", "SHMONAD", 11, 12),
+        ("mint", "NAD DOMAIN", 13, 14),
+    ]
+    .map(|(action, protocol, current, target)| {
+        (action.to_string(), protocol.to_string(), current, target)
+    });
+    assert_eq!(rows, expected);
+
+    // the counts are gone from the account table
+    assert!(
+        db.execute_unprepared("SELECT current_ambient_swaps_count FROM account").await.is_err()
+    );
+
+    // and every moved goal is one the software knows
+    let repo = create_repositories_at(&file.0).await.unwrap();
+    let goals = account_goals::search_by_account_id(repo, 1).await.unwrap();
+    assert_eq!(goals.len(), expected.len());
+    assert!(goals.iter().all(|goal| goal.account_action().is_some()));
+}
@@ -0,0 +1,32 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Account::Table)
+                    .add_column(boolean(Account::Active).default(true))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter().table(Account::Table).drop_column(Account::Active).to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Account {
+    Table,
+    Active,
+}
@@ -0,0 +1,124 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// The action and protocol of every goal that used to be a pair of columns on the account
+/// table, followed by the names of those columns.
+const LEGACY_GOALS: [(&str, &str, &str, &str); 7] = [
+    ("swap", "AMBIENT", "current_ambient_swaps_count", "target_ambient_swaps_count"),
+    ("swap", "BEAN", "current_bean_swaps_count", "target_bean_swaps_count"),
+    ("swap", "HASHFLOW", "current_hashflow_swaps_count", "target_hashflow_swaps_count"),
+    ("lending", "APRIORI", "current_apriori_deposit_count", "target_apriori_deposit_count"),
+    ("lending", "KINZA", "current_kinza_deposit_count", "target_kinza_deposit_count"),
+    ("lending", "SHMONAD", "current_shmonad_deposit_count", "target_shmonad_deposit_count"),
+    ("mint", "NAD DOMAIN", "current_nad_domains_count", "target_nad_domains_count"),
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AccountGoal::Table)
+                    .if_not_exists()
+                    .col(pk_auto(AccountGoal::Id))
+                    .col(integer(AccountGoal::AccountId))
+                    .col(string(AccountGoal::Action))
+                    .col(string(AccountGoal::Protocol))
+                    .col(integer(AccountGoal::Current).default(0))
+                    .col(integer(AccountGoal::Target))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-account_goal-account_id")
+                            .from(AccountGoal::Table, AccountGoal::AccountId)
+                            .to(Account::Table, Account::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-account_goal-account_id-action-protocol")
+                    .table(AccountGoal::Table)
+                    .col(AccountGoal::AccountId)
+                    .col(AccountGoal::Action)
+                    .col(AccountGoal::Protocol)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+
+        for (action, protocol, current, target) in LEGACY_GOALS {
+            db.execute_unprepared(&format!(
+                "INSERT INTO account_goal (account_id, action, protocol, current, target) \
+                 SELECT id, '{action}', '{protocol}', {current}, {target} FROM account"
+            ))
+            .await?;
+
+            for column in [current, target] {
+                manager
+                    .alter_table(
+                        Table::alter()
+                            .table(Account::Table)
+                            .drop_column(Alias::new(column))
+                            .to_owned(),
+                    )
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        for (action, protocol, current, target) in LEGACY_GOALS {
+            for column in [current, target] {
+                manager
+                    .alter_table(
+                        Table::alter()
+                            .table(Account::Table)
+                            .add_column(integer(Alias::new(column)).default(0))
+                            .to_owned(),
+                    )
+                    .await?;
+            }
+
+            db.execute_unprepared(&format!(
+                "UPDATE account SET \
+                 {current} = goal.current, {target} = goal.target \
+                 FROM (SELECT account_id, current, target FROM account_goal \
+                 WHERE action = '{action}' AND protocol = '{protocol}') AS goal \
+                 WHERE goal.account_id = account.id"
+            ))
+            .await?;
+        }
+
+        manager.drop_table(Table::drop().table(AccountGoal::Table).to_owned()).await
+    }
+}
+
+#[derive(DeriveIden)]
+enum AccountGoal {
+    Table,
+    Id,
+    AccountId,
+    Action,
+    Protocol,
+    Current,
+    Target,
+}
+
+#[derive(DeriveIden)]
+enum Account {
+    Table,
+    Id,
+}
@@ -0,0 +1,64 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BalanceSnapshot::Table)
+                    .add_column(integer(BalanceSnapshot::SnapshotId).default(0))
+                    .to_owned(),
+            )
+            .await?;
+
+        // the snapshots taken so far were told apart by their time, they keep their numbers
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE balance_snapshot SET snapshot_id = (SELECT COUNT(DISTINCT earlier.taken_at) \
+                 FROM balance_snapshot AS earlier WHERE earlier.taken_at <= \
+                 balance_snapshot.taken_at)",
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-balance_snapshot-snapshot_id")
+                    .table(BalanceSnapshot::Table)
+                    .col(BalanceSnapshot::SnapshotId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-balance_snapshot-snapshot_id")
+                    .table(BalanceSnapshot::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BalanceSnapshot::Table)
+                    .drop_column(BalanceSnapshot::SnapshotId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum BalanceSnapshot {
+    Table,
+    SnapshotId,
+}
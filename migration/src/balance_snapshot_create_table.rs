@@ -0,0 +1,62 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BalanceSnapshot::Table)
+                    .if_not_exists()
+                    .col(pk_auto(BalanceSnapshot::Id))
+                    .col(integer(BalanceSnapshot::AccountId))
+                    .col(big_integer(BalanceSnapshot::ChainId))
+                    .col(string(BalanceSnapshot::Token))
+                    .col(string(BalanceSnapshot::Balance))
+                    .col(date_time(BalanceSnapshot::TakenAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-balance_snapshot-account_id")
+                            .from(BalanceSnapshot::Table, BalanceSnapshot::AccountId)
+                            .to(Account::Table, Account::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-balance_snapshot-taken_at")
+                    .table(BalanceSnapshot::Table)
+                    .col(BalanceSnapshot::TakenAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(BalanceSnapshot::Table).to_owned()).await
+    }
+}
+
+#[derive(DeriveIden)]
+enum BalanceSnapshot {
+    Table,
+    Id,
+    AccountId,
+    ChainId,
+    Token,
+    Balance,
+    TakenAt,
+}
+
+#[derive(DeriveIden)]
+enum Account {
+    Table,
+    Id,
+}
@@ -1,12 +1,32 @@
 pub use sea_orm_migration::prelude::*;
 
+mod account_add_active;
+mod account_add_failure_count;
+mod account_add_inactive_reason;
+mod account_add_resume_delay;
 mod account_create_table;
+mod account_goal_create_table;
+mod balance_snapshot_add_snapshot_id;
+mod balance_snapshot_create_table;
+mod transaction_add_attempts;
+mod transaction_create_table;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(account_create_table::Migration)]
+        vec![
+            Box::new(account_create_table::Migration),
+            Box::new(transaction_create_table::Migration),
+            Box::new(transaction_add_attempts::Migration),
+            Box::new(account_add_active::Migration),
+            Box::new(account_goal_create_table::Migration),
+            Box::new(account_add_resume_delay::Migration),
+            Box::new(account_add_failure_count::Migration),
+            Box::new(balance_snapshot_create_table::Migration),
+            Box::new(account_add_inactive_reason::Migration),
+            Box::new(balance_snapshot_add_snapshot_id::Migration),
+        ]
     }
 }
@@ -0,0 +1,66 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Transaction::Table)
+                    .if_not_exists()
+                    .col(pk_auto(Transaction::Id))
+                    .col(integer(Transaction::AccountId))
+                    .col(big_integer(Transaction::ChainId))
+                    .col(string(Transaction::Action))
+                    .col(string(Transaction::Protocol))
+                    .col(string_null(Transaction::TokenIn))
+                    .col(string_null(Transaction::TokenOut))
+                    .col(string_null(Transaction::AmountIn))
+                    .col(string_null(Transaction::TxHash))
+                    .col(string(Transaction::Status))
+                    .col(string_null(Transaction::GasCost))
+                    .col(string_null(Transaction::Error))
+                    .col(date_time(Transaction::CreatedAt).default(Expr::current_timestamp()))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-transaction-account_id")
+                            .from(Transaction::Table, Transaction::AccountId)
+                            .to(Account::Table, Account::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(Transaction::Table).to_owned()).await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Transaction {
+    Table,
+    Id,
+    AccountId,
+    ChainId,
+    Action,
+    Protocol,
+    TokenIn,
+    TokenOut,
+    AmountIn,
+    TxHash,
+    Status,
+    GasCost,
+    Error,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Account {
+    Table,
+    Id,
+}
@@ -1,4 +1,5 @@
-use clap::{Parser, ValueEnum};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
 
 #[derive(ValueEnum, Clone, Debug, Default)]
 pub enum LogLevel {
@@ -10,17 +11,132 @@ pub enum LogLevel {
     Trace,
 }
 
-/// cargo run -- -l error | warn | info | debug | trace
+/// cargo run -- -l error | warn | info | debug | trace [COMMAND]
+///
+/// Without a command the interactive menu is shown.
 #[derive(Parser, Debug, Default)]
-#[command(about = "Sets the level of logging (error, warn, info, debug, trace)")]
+#[command(about = "Monad testnet farming software")]
 pub struct Cli {
-    #[arg(short, long, value_enum, default_value_t = LogLevel::Info)]
+    /// Sets the level of logging (error, warn, info, debug, trace)
+    #[arg(short, long, value_enum, default_value_t = LogLevel::Info, global = true)]
     pub log_level: LogLevel,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Manages the accounts database
+    #[command(subcommand)]
+    Db(DbCommand),
+
+    /// Runs the warmup for every account that hasn't reached its goal yet
+    Warmup {
+        /// Simulates the transactions on a copy of the database instead of sending them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Prints the progress of every account along with the totals
+    Status,
+
+    /// Withdraws every lending position of the given accounts, or of every account
+    Unwind {
+        /// Addresses of the accounts to unwind
+        addresses: Vec<String>,
+    },
+
+    /// Takes and shows snapshots of the token balances of every account
+    #[command(subcommand)]
+    Balances(BalancesCommand),
+}
+
+#[derive(Subcommand, Debug)]
+pub enum BalancesCommand {
+    /// Fetches every token balance on Monad and Base and stores it as a new snapshot
+    Take,
+
+    /// Lists the snapshots taken so far, numbered from the oldest
+    List,
+
+    /// Prints a snapshot, the latest by default
+    Show {
+        /// Number of the snapshot as shown by `balances list`
+        #[arg(long)]
+        snapshot: Option<i32>,
+
+        /// Writes the snapshot to a .csv or .json file instead
+        #[arg(long)]
+        export: Option<PathBuf>,
+    },
+
+    /// Prints the balances that changed between two snapshots, the two latest by default
+    Diff {
+        /// Number of the older snapshot, the one before `--to` by default
+        #[arg(long)]
+        from: Option<i32>,
+
+        /// Number of the newer snapshot, the latest by default
+        #[arg(long)]
+        to: Option<i32>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DbCommand {
+    /// Adds accounts from `data/private_keys.txt` and `data/proxies.txt`
+    Generate,
+
+    /// Adds new keys, updates proxies and marks removed keys inactive, keeping the progress
+    Sync {
+        /// Draws new targets from the config for every listed account
+        #[arg(long)]
+        reroll_targets: bool,
+    },
+
+    /// Clears the database and generates it again
+    Reset,
+
+    /// Removes every account from the database
+    Clear,
+
+    /// Writes every account to a .csv or .json file
+    Export {
+        path: PathBuf,
+
+        /// Writes the private keys as well
+        #[arg(long)]
+        include_private_keys: bool,
+    },
+
+    /// Adds or updates accounts from a .csv or .json file, matching them by address
+    Import { path: PathBuf },
+}
+
+impl Command {
+    /// Whether the command reads or writes private keys, which needs the passphrase.
+    pub fn needs_private_keys(&self) -> bool {
+        match self {
+            Command::Db(DbCommand::Export { include_private_keys, .. }) => *include_private_keys,
+            Command::Db(DbCommand::Clear) | Command::Status | Command::Balances(_) => false,
+            Command::Db(_) | Command::Warmup { .. } | Command::Unwind { .. } => true,
+        }
+    }
+
+    /// Whether the command touches the chain, which needs a valid `data/tokens.toml`.
+    pub fn needs_tokens(&self) -> bool {
+        matches!(
+            self,
+            Command::Warmup { .. } |
+                Command::Unwind { .. } |
+                Command::Balances(BalancesCommand::Take)
+        )
+    }
 }
 
 pub fn parse_cli_args() -> Cli {
-    Cli::try_parse().unwrap_or_else(|e| {
-        eprintln!("Invalid CLI args provided: {e}. Using default");
-        Cli::default()
-    })
+    // invalid args exit with a non-zero status code, so a typo in a cron job
+    // doesn't fall through to the interactive menu
+    Cli::parse()
 }
@@ -1,3 +1,4 @@
+use common::error::ErrorClass;
 use thiserror::Error;
 
 use crate::modules::warmup::error::WarmupError;
@@ -26,3 +27,15 @@ pub enum Error {
     #[error(transparent)]
     UrlParse(#[from] url::ParseError),
 }
+
+impl Error {
+    /// Decides how often an account tries again after failing with the error.
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            Error::Common(e) => e.class(),
+            Error::Warmup(e) => e.class(),
+            Error::DatabaseError(e) => e.class(),
+            Error::MenuError(_) | Error::Io(_) | Error::UrlParse(_) => ErrorClass::Permanent,
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use cli::parse_cli_args;
 use logger::init_logging;
-use modules::menu;
+use modules::{commands, menu};
+use std::process::ExitCode;
 
 pub use crate::error::{Error, Result};
 
@@ -10,13 +11,20 @@ mod logger;
 mod modules;
 
 #[tokio::main]
-async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+async fn main() -> ExitCode {
     let cli = parse_cli_args();
     init_logging(&cli.log_level);
 
-    if let Err(e) = menu::menu().await {
-        tracing::error!("Stopped with error: {e}")
-    }
+    let res = match cli.command {
+        Some(command) => commands::run(command).await,
+        None => menu::menu().await,
+    };
 
-    Ok(())
+    match res {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            tracing::error!("Stopped with error: {e}");
+            ExitCode::FAILURE
+        }
+    }
 }
@@ -0,0 +1,290 @@
+use alloy::primitives::U256;
+use alloy_chains::NamedChain;
+use common::{
+    config::Config,
+    onchain::token::{Token, balances_of},
+};
+use database::{
+    entity::impls::{
+        account::AccountConditions,
+        balance_snapshot::{AccountBalance, Snapshot, format_amount},
+        prelude::BalanceSnapshotList,
+    },
+    export::export_balances,
+    repositories::RepoImpls,
+    use_cases::{accounts, balance_snapshots},
+};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use super::{
+    status::{column_widths, print_row},
+    warmup::connect,
+};
+use crate::Result;
+
+/// The snapshot with the given number, or the latest one.
+fn find(snapshots: &[Snapshot], number: Option<i32>) -> Option<Snapshot> {
+    match number {
+        Some(number) => snapshots.iter().find(|snapshot| snapshot.id == number).copied(),
+        None => snapshots.last().copied(),
+    }
+}
+
+/// The account and chain of every balance, i.e. the chains the balances of an account were
+/// fetched on.
+fn fetched(balances: &BalanceSnapshotList) -> BTreeSet<(i32, i64)> {
+    balances.iter().map(|balance| (balance.account_id, balance.chain_id)).collect()
+}
+
+fn chain_name(chain_id: i64) -> String {
+    NamedChain::try_from(chain_id as u64)
+        .map(|chain| chain.to_string())
+        .unwrap_or_else(|_| chain_id.to_string())
+}
+
+fn print_table(header: &[String], rows: &[Vec<String>]) {
+    let widths = column_widths(header, rows);
+    let separator = "-".repeat(widths.iter().sum::<usize>() + 3 * (widths.len() - 1));
+
+    print_row(header, &widths);
+    println!("{separator}");
+    rows.iter().for_each(|row| print_row(row, &widths));
+}
+
+/// The tokens of the snapshot in the order of the registry, tokens that aren't registered anymore
+/// last.
+fn tokens_of(balances: &BalanceSnapshotList) -> Vec<String> {
+    let mut tokens = Token::all()
+        .map(|token| token.ticker().to_string())
+        .filter(|ticker| balances.iter().any(|balance| balance.token == *ticker))
+        .collect::<Vec<_>>();
+
+    for balance in balances {
+        if !tokens.contains(&balance.token) {
+            tokens.push(balance.token.clone());
+        }
+    }
+
+    tokens
+}
+
+async fn addresses(repo: Arc<RepoImpls>) -> Result<HashMap<i32, String>> {
+    Ok(accounts::search(repo, AccountConditions::default())
+        .await?
+        .into_iter()
+        .map(|account| (account.id, account.address))
+        .collect())
+}
+
+/// Fetches the balance of every token on Monad and Base for every account, stores them as a new
+/// snapshot and prints it. An account whose balances can't be fetched on a chain is left out of
+/// the snapshot for that chain, so a diff doesn't compare that chain for it.
+pub async fn take_snapshot(repo: Arc<RepoImpls>, config: Arc<Config>) -> Result<()> {
+    let accounts = accounts::search(repo.clone(), AccountConditions::default()).await?;
+
+    if accounts.is_empty() {
+        tracing::warn!("No accounts in the database");
+        return Ok(());
+    }
+
+    let monad =
+        connect(NamedChain::MonadTestnet, &config.monad_rpc_urls, config.monad_max_tps, &config)?;
+    let base = connect(NamedChain::Base, &config.base_rpc_urls, config.base_max_tps, &config)?;
+
+    let mut balances = vec![];
+
+    for account in &accounts {
+        for (chain, connection) in [(NamedChain::MonadTestnet, &monad), (NamedChain::Base, &base)] {
+            match balances_of(&connection.provider, chain, account.address()).await {
+                Ok(tokens) => balances.extend(tokens.into_iter().map(|(token, balance)| {
+                    AccountBalance { account_id: account.id, token, balance }
+                })),
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to fetch the balances of `{}` on {chain}: {e}",
+                        account.address
+                    )
+                }
+            }
+        }
+    }
+
+    if balances.is_empty() {
+        tracing::warn!("No balances fetched, no snapshot taken");
+        return Ok(());
+    }
+
+    let snapshot = balance_snapshots::add(repo.clone(), balances).await?;
+    tracing::info!(
+        "Took balance snapshot #{} of {} accounts at {}",
+        snapshot.id,
+        accounts.len(),
+        snapshot.taken_at
+    );
+
+    show_snapshot(repo, None, None).await
+}
+
+/// Prints the number and time of every snapshot, oldest first.
+pub async fn list_snapshots(repo: Arc<RepoImpls>) -> Result<()> {
+    let snapshots = balance_snapshots::list(repo).await?;
+
+    if snapshots.is_empty() {
+        println!("No balance snapshots taken yet");
+        return Ok(());
+    }
+
+    let rows = snapshots
+        .iter()
+        .map(|snapshot| vec![snapshot.id.to_string(), snapshot.taken_at.to_string()])
+        .collect::<Vec<_>>();
+
+    print_table(&["#".to_string(), "TAKEN AT".to_string()], &rows);
+
+    Ok(())
+}
+
+/// Prints the balances of a snapshot, the latest by default, or writes them to a `.csv` or
+/// `.json` file.
+pub async fn show_snapshot(
+    repo: Arc<RepoImpls>,
+    number: Option<i32>,
+    export: Option<PathBuf>,
+) -> Result<()> {
+    let snapshots = balance_snapshots::list(repo.clone()).await?;
+
+    let Some(snapshot) = find(&snapshots, number) else {
+        println!("No such balance snapshot, `balances list` shows the ones taken");
+        return Ok(());
+    };
+
+    if let Some(path) = export {
+        let count = export_balances(repo, &path, snapshot.id).await?;
+        tracing::info!("Exported {count} balances to `{}`", path.display());
+        return Ok(());
+    }
+
+    let balances = balance_snapshots::of(repo.clone(), snapshot.id).await?;
+    let addresses = addresses(repo).await?;
+    let tokens = tokens_of(&balances);
+
+    // amount by account and token
+    let mut amounts = BTreeMap::<i32, HashMap<&str, String>>::new();
+    for balance in &balances {
+        amounts.entry(balance.account_id).or_default().insert(&balance.token, balance.amount());
+    }
+
+    let mut header = vec!["ID".to_string(), "ADDRESS".to_string()];
+    header.extend(tokens.iter().map(|token| token.to_uppercase()));
+
+    let rows = amounts
+        .iter()
+        .map(|(account_id, amounts)| {
+            let mut row = vec![
+                account_id.to_string(),
+                addresses.get(account_id).cloned().unwrap_or_default(),
+            ];
+            row.extend(tokens.iter().map(|token| {
+                amounts.get(token.as_str()).cloned().unwrap_or_else(|| "-".to_string())
+            }));
+            row
+        })
+        .collect::<Vec<_>>();
+
+    println!("Snapshot #{} taken at {}", snapshot.id, snapshot.taken_at);
+    print_table(&header, &rows);
+
+    Ok(())
+}
+
+/// Prints every balance that changed from one snapshot to another, by default from the one before
+/// the latest to the latest.
+///
+/// Only the chains an account's balances were fetched on in both snapshots are compared, the
+/// others are listed after the changes.
+pub async fn diff_snapshots(
+    repo: Arc<RepoImpls>,
+    from: Option<i32>,
+    to: Option<i32>,
+) -> Result<()> {
+    let snapshots = balance_snapshots::list(repo.clone()).await?;
+
+    let to = find(&snapshots, to);
+    let from = match from {
+        Some(_) => find(&snapshots, from),
+        None => to.and_then(|to| {
+            snapshots.iter().take_while(|snapshot| snapshot.id != to.id).last().copied()
+        }),
+    };
+
+    let (Some(from), Some(to)) = (from, to) else {
+        println!("A diff needs two balance snapshots, `balances list` shows the ones taken");
+        return Ok(());
+    };
+
+    let before = balance_snapshots::of(repo.clone(), from.id).await?;
+    let after = balance_snapshots::of(repo.clone(), to.id).await?;
+    let addresses = addresses(repo).await?;
+
+    let (fetched_before, fetched_after) = (fetched(&before), fetched(&after));
+    let compared = fetched_before.intersection(&fetched_after).collect::<BTreeSet<_>>();
+
+    // balance before and after by account and token, a missing one counting as zero on a chain
+    // that was fetched both times
+    let mut changes = BTreeMap::<(i32, &str), (U256, U256)>::new();
+    for balance in before.iter().filter(|b| compared.contains(&(b.account_id, b.chain_id))) {
+        changes.entry((balance.account_id, &balance.token)).or_default().0 = balance.balance();
+    }
+    for balance in after.iter().filter(|b| compared.contains(&(b.account_id, b.chain_id))) {
+        changes.entry((balance.account_id, &balance.token)).or_default().1 = balance.balance();
+    }
+
+    let rows = changes
+        .into_iter()
+        .filter(|(_, (before, after))| before != after)
+        .map(|((account_id, token), (before, after))| {
+            let change = match after > before {
+                true => format!("+{}", format_amount(token, after - before)),
+                false => format!("-{}", format_amount(token, before - after)),
+            };
+
+            vec![
+                account_id.to_string(),
+                addresses.get(&account_id).cloned().unwrap_or_default(),
+                token.to_string(),
+                format_amount(token, before),
+                format_amount(token, after),
+                change,
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    println!(
+        "Balances from snapshot #{} ({}) to #{} ({})",
+        from.id, from.taken_at, to.id, to.taken_at
+    );
+
+    match rows.is_empty() {
+        true => println!("No balance changed"),
+        false => {
+            let header = ["ID", "ADDRESS", "TOKEN", "BEFORE", "AFTER", "CHANGE"].map(String::from);
+            print_table(&header, &rows);
+        }
+    }
+
+    let missing = fetched_before.symmetric_difference(&fetched_after).collect::<BTreeSet<_>>();
+    if !missing.is_empty() {
+        println!();
+        println!("Not compared, fetched in only one of the snapshots:");
+        for (account_id, chain_id) in missing {
+            let address = addresses.get(account_id).map(String::as_str).unwrap_or_default();
+            println!("  {account_id} {address} on {}", chain_name(*chain_id));
+        }
+    }
+
+    Ok(())
+}
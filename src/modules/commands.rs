@@ -0,0 +1,101 @@
+use crate::{
+    Result,
+    cli::{BalancesCommand, Command, DbCommand},
+};
+use common::{config::Config, onchain::token::TokenRegistry};
+use database::{
+    db::{clear, generate, sync},
+    export::{export, import},
+    repositories::{RepoImpls, create_repositories},
+};
+use std::sync::Arc;
+
+use super::{
+    balances::{diff_snapshots, list_snapshots, show_snapshot, take_snapshot},
+    status::status,
+    unlock::unlock,
+    unwind::unwind,
+    warmup::run_warmup,
+};
+
+/// Runs a single command without the interactive menu.
+pub async fn run(command: Command) -> Result<()> {
+    let repo = create_repositories().await?;
+    let config = Arc::new(Config::read_default().await);
+
+    // a broken token registry only stops the commands that touch the chain, so that the database
+    // can still be exported, cleared or reset
+    if let Err(e) = TokenRegistry::read_default().await {
+        match command.needs_tokens() {
+            true => return Err(e.into()),
+            false => tracing::warn!("Failed to read `data/tokens.toml`: {e}"),
+        }
+    }
+
+    if command.needs_private_keys() {
+        unlock(Arc::clone(&repo)).await?;
+    }
+
+    match command {
+        Command::Db(DbCommand::Generate) => generate(repo, config).await?,
+        Command::Db(DbCommand::Sync { reroll_targets }) => {
+            sync_db(repo, config, reroll_targets).await?
+        }
+        Command::Db(DbCommand::Reset) => reset(repo, config).await?,
+        Command::Db(DbCommand::Clear) => clear(repo).await?,
+        Command::Db(DbCommand::Export { path, include_private_keys }) => {
+            let count = export(repo, &path, include_private_keys).await?;
+            tracing::info!("Exported {count} accounts to `{}`", path.display());
+        }
+        Command::Db(DbCommand::Import { path }) => {
+            let summary = import(repo, config, &path).await?;
+            tracing::info!(
+                "Imported `{}`: {} added, {} updated, {} skipped",
+                path.display(),
+                summary.added,
+                summary.updated,
+                summary.skipped
+            );
+        }
+        Command::Warmup { dry_run } => run_warmup(repo, config, dry_run).await?,
+        Command::Status => status(repo).await?,
+        Command::Unwind { addresses } => unwind(repo, config, addresses).await?,
+        Command::Balances(BalancesCommand::Take) => take_snapshot(repo, config).await?,
+        Command::Balances(BalancesCommand::List) => list_snapshots(repo).await?,
+        Command::Balances(BalancesCommand::Show { snapshot, export }) => {
+            show_snapshot(repo, snapshot, export).await?
+        }
+        Command::Balances(BalancesCommand::Diff { from, to }) => {
+            diff_snapshots(repo, from, to).await?
+        }
+    }
+
+    Ok(())
+}
+
+/// Syncs the database with the key and proxy files and logs what changed.
+pub async fn sync_db(
+    repo: Arc<RepoImpls>,
+    config: Arc<Config>,
+    reroll_targets: bool,
+) -> Result<()> {
+    let summary = sync(repo, config, reroll_targets).await?;
+    tracing::info!(
+        "Synced the database: {} added, {} updated, {} deactivated, {} unchanged, {} failed",
+        summary.added,
+        summary.updated,
+        summary.deactivated,
+        summary.unchanged,
+        summary.failed
+    );
+
+    Ok(())
+}
+
+/// Removes every account and generates the database again.
+pub async fn reset(repo: Arc<RepoImpls>, config: Arc<Config>) -> Result<()> {
+    clear(Arc::clone(&repo)).await?;
+    generate(repo, config).await?;
+
+    Ok(())
+}
@@ -1,20 +1,45 @@
 use crate::Result;
-use common::config::Config;
-use database::{
-    db::{clear, generate},
-    repositories::create_repositories,
-};
-use dialoguer::{Select, theme::ColorfulTheme};
+use common::{config::Config, onchain::token::TokenRegistry};
+use database::{db::generate, repositories::create_repositories};
+use dialoguer::{Confirm, Select, theme::ColorfulTheme};
 use std::sync::Arc;
 
-use super::warmup::run_warmup;
+use super::{
+    balances::{diff_snapshots, take_snapshot},
+    commands::{reset, sync_db},
+    status::status,
+    unlock::unlock,
+    unwind::unwind_selected,
+    warmup::run_warmup,
+};
 
 pub async fn menu() -> Result<()> {
     let repo = Arc::new(create_repositories().await?);
     let config = Arc::new(Config::read_default().await);
 
+    // the database options keep working with a broken token registry
+    let tokens = TokenRegistry::read_default().await;
+    if let Err(e) = &tokens {
+        tracing::warn!(
+            "Failed to read `data/tokens.toml`: {e}, warmups, unwinds and balance snapshots are \
+             disabled until it's fixed"
+        );
+    }
+
+    unlock(Arc::clone(&repo)).await?;
+
     loop {
-        let options = vec!["Generate DB", "Update DB", "Warmup", "Exit"];
+        let options = vec![
+            "Generate DB",
+            "Update DB",
+            "Reset DB",
+            "Warmup",
+            "Warmup (dry run)",
+            "Status",
+            "Unwind lending",
+            "Balances",
+            "Exit",
+        ];
 
         let selection = Select::with_theme(&ColorfulTheme::default())
             .with_prompt("Choice:")
@@ -23,13 +48,30 @@ pub async fn menu() -> Result<()> {
             .interact()?;
 
         match selection {
+            3 | 4 | 6 | 7 if tokens.is_err() => {
+                tracing::error!(
+                    "`data/tokens.toml` is invalid, fix it and restart to use this option"
+                )
+            }
             0 => generate(Arc::clone(&repo), Arc::clone(&config)).await?,
             1 => {
-                clear(Arc::clone(&repo)).await?;
-                generate(Arc::clone(&repo), Arc::clone(&config)).await?;
+                let reroll_targets = Confirm::with_theme(&ColorfulTheme::default())
+                    .with_prompt("Draw new targets from the config?")
+                    .default(false)
+                    .interact()?;
+
+                sync_db(Arc::clone(&repo), Arc::clone(&config), reroll_targets).await?
+            }
+            2 => reset(Arc::clone(&repo), Arc::clone(&config)).await?,
+            3 => run_warmup(Arc::clone(&repo), Arc::clone(&config), false).await?,
+            4 => run_warmup(Arc::clone(&repo), Arc::clone(&config), true).await?,
+            5 => status(Arc::clone(&repo)).await?,
+            6 => unwind_selected(Arc::clone(&repo), Arc::clone(&config)).await?,
+            7 => {
+                take_snapshot(Arc::clone(&repo), Arc::clone(&config)).await?;
+                diff_snapshots(Arc::clone(&repo), None, None).await?
             }
-            2 => run_warmup(Arc::clone(&repo), Arc::clone(&config)).await?,
-            3 => return Ok(()),
+            8 => return Ok(()),
             _ => tracing::error!("Invalid selection"),
         }
     }
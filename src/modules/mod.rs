@@ -1,2 +1,7 @@
+pub mod balances;
+pub mod commands;
 pub mod menu;
+pub mod status;
+pub mod unlock;
+pub mod unwind;
 pub mod warmup;
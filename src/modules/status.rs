@@ -0,0 +1,153 @@
+use database::{
+    entity::impls::{
+        account::AccountConditions,
+        account_goal::{AccountGoalConditions, GOALS},
+    },
+    repositories::RepoImpls,
+    use_cases::{account_goals, accounts},
+};
+use std::{collections::HashMap, sync::Arc};
+
+use crate::Result;
+
+fn percent(done: i64, total: i64) -> f64 {
+    match total {
+        0 => 100.0,
+        _ => done as f64 * 100.0 / total as f64,
+    }
+}
+
+fn yes_no(value: bool) -> String {
+    match value {
+        true => "yes".to_string(),
+        false => "no".to_string(),
+    }
+}
+
+/// The width of every column, wide enough for the header and every row.
+pub(super) fn column_widths<'a>(
+    header: &[String],
+    rows: impl IntoIterator<Item = &'a Vec<String>> + Clone,
+) -> Vec<usize> {
+    header
+        .iter()
+        .enumerate()
+        .map(|(i, cell)| {
+            rows.clone().into_iter().map(|row| row[i].len()).max().unwrap_or(0).max(cell.len())
+        })
+        .collect()
+}
+
+pub(super) fn print_row(cells: &[String], widths: &[usize]) {
+    let row = cells
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| format!("{cell:<width$}"))
+        .collect::<Vec<_>>()
+        .join(" | ");
+
+    println!("{}", row.trim_end());
+}
+
+/// Prints the current and target count of every action per account, followed by the completion
+/// of each action and the number of actions left across all accounts, and why each inactive
+/// account is out of the warmup.
+pub async fn status(repo: Arc<RepoImpls>) -> Result<()> {
+    let accounts = accounts::search(repo.clone(), AccountConditions::default()).await?;
+
+    if accounts.is_empty() {
+        println!("No accounts in the database");
+        return Ok(());
+    }
+
+    // current and target count by account and goal key
+    let counts = account_goals::search(repo, AccountGoalConditions::default())
+        .await?
+        .into_iter()
+        .filter_map(|goal| {
+            let key = goal.account_action()?.goal_key();
+            Some(((goal.account_id, key), (goal.current, goal.target)))
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut header = vec!["ID".to_string(), "ADDRESS".to_string()];
+    header.extend(GOALS.iter().map(|goal| goal.action.goal_key().to_uppercase()));
+    header.extend(["BRIDGE".to_string(), "DONE".to_string()]);
+
+    // done and target count of every action over all accounts
+    let mut totals = vec![(0i64, 0i64); GOALS.len()];
+    let mut bridged = 0;
+    let mut finished = 0;
+    let mut inactive = 0;
+
+    let mut rows = vec![];
+
+    for account in &accounts {
+        let mut row = vec![account.id.to_string(), account.address.clone()];
+
+        for (i, goal) in GOALS.iter().enumerate() {
+            let (current, target) =
+                counts.get(&(account.id, goal.action.goal_key())).copied().unwrap_or_default();
+            row.push(format!("{current}/{target}"));
+            totals[i].0 += current.min(target) as i64;
+            totals[i].1 += target as i64;
+        }
+
+        let done = match account.active {
+            true => yes_no(account.goal_reached),
+            false => "inactive".to_string(),
+        };
+        row.extend([yes_no(account.bridge_goal), done]);
+        rows.push(row);
+
+        bridged += account.bridge_goal as i64;
+        finished += (account.active && account.goal_reached) as i64;
+        inactive += !account.active as i64;
+    }
+
+    let count = accounts.len() as i64;
+
+    let mut total_row = vec!["TOTAL".to_string(), String::new()];
+    total_row
+        .extend(totals.iter().map(|(done, target)| format!("{:.0}%", percent(*done, *target))));
+    total_row.extend([
+        format!("{:.0}%", percent(bridged, count)),
+        format!("{:.0}%", percent(finished, count)),
+    ]);
+
+    let widths = column_widths(&header, rows.iter().chain([&total_row]));
+
+    let separator = "-".repeat(widths.iter().sum::<usize>() + 3 * (widths.len() - 1));
+
+    print_row(&header, &widths);
+    println!("{separator}");
+    rows.iter().for_each(|row| print_row(row, &widths));
+    println!("{separator}");
+    print_row(&total_row, &widths);
+
+    // the bridge counts as an action of its own
+    let done = totals.iter().map(|(done, _)| done).sum::<i64>() + bridged;
+    let target = totals.iter().map(|(_, target)| target).sum::<i64>() + count;
+
+    println!();
+    println!(
+        "Accounts: {count}, finished: {finished}, in progress: {}, inactive: {inactive}",
+        count - finished - inactive
+    );
+    println!(
+        "Actions: {done}/{target} ({:.1}%), remaining: {}",
+        percent(done, target),
+        target - done
+    );
+
+    if inactive > 0 {
+        println!();
+        println!("Inactive accounts:");
+        for account in accounts.iter().filter(|account| !account.active) {
+            let reason = account.inactive_reason.as_deref().unwrap_or("unknown");
+            println!("  {} {}: {reason}", account.id, account.address);
+        }
+    }
+
+    Ok(())
+}
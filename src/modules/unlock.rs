@@ -0,0 +1,46 @@
+use database::{
+    crypto::{self, PASSPHRASE_VAR},
+    db::{encrypt_private_keys, has_encrypted_keys},
+    repositories::RepoImpls,
+};
+use dialoguer::{Password, theme::ColorfulTheme};
+use std::sync::Arc;
+
+use crate::Result;
+
+/// Reads the passphrase the private keys are encrypted with from `MONAD_PULSE_PASSPHRASE`, or
+/// asks for it, and encrypts the keys that are still stored in plain text.
+///
+/// A new passphrase has to be typed twice. A wrong one fails before anything else runs.
+pub async fn unlock(repo: Arc<RepoImpls>) -> Result<()> {
+    if crypto::is_unlocked() {
+        return Ok(());
+    }
+
+    let passphrase = match std::env::var(PASSPHRASE_VAR) {
+        Ok(passphrase) if !passphrase.is_empty() => passphrase,
+        _ => {
+            let theme = ColorfulTheme::default();
+            let mut prompt = Password::with_theme(&theme);
+
+            if has_encrypted_keys(Arc::clone(&repo)).await? {
+                prompt = prompt.with_prompt("Passphrase");
+            } else {
+                prompt = prompt
+                    .with_prompt("New passphrase for the private keys")
+                    .with_confirmation("Repeat the passphrase", "The passphrases don't match");
+            }
+
+            prompt.interact()?
+        }
+    };
+
+    crypto::unlock(passphrase);
+
+    let count = encrypt_private_keys(repo).await?;
+    if count > 0 {
+        tracing::info!("Encrypted {count} private keys stored in plain text");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use alloy::providers::RootProvider;
+use alloy_chains::NamedChain;
+use common::{
+    config::{Config, NonceManagerKind},
+    onchain::{
+        action::{prepare, registry},
+        client::{
+            CachedNonceManager, Client as EvmClient, ClientNonceManager, PendingNonceManager,
+            StrictNonceManager,
+        },
+        token::verify_tokens,
+    },
+};
+use database::{
+    entity::impls::{account::AccountConditions, prelude::AccountModel},
+    repositories::RepoImpls,
+    use_cases::{accounts, transactions},
+};
+use dialoguer::{MultiSelect, theme::ColorfulTheme};
+use std::sync::Arc;
+
+use super::warmup::{Connection, connect, run_action};
+use crate::Result;
+
+/// How many times an exit runs for an account at most, e.g. an Apriori claim followed by a new
+/// request.
+const MAX_EXIT_STEPS: usize = 3;
+
+/// Exits every lending position of the accounts with the given addresses, or of every account
+/// if none are given.
+///
+/// shMonad and Kinza positions are withdrawn right away. Apriori processes withdrawals in the
+/// background, so the first unwind requests one and a later unwind claims the MON once it's
+/// processed. The transactions go to the transaction history but don't count towards the goals.
+pub async fn unwind(
+    repo: Arc<RepoImpls>,
+    config: Arc<Config>,
+    addresses: Vec<String>,
+) -> Result<()> {
+    let accounts = accounts::search(repo.clone(), AccountConditions::default())
+        .await?
+        .into_iter()
+        .filter(|account| {
+            addresses.is_empty() ||
+                addresses.iter().any(|address| address.eq_ignore_ascii_case(&account.address))
+        })
+        .collect::<Vec<_>>();
+
+    if accounts.is_empty() {
+        tracing::warn!("No accounts to unwind");
+        return Ok(());
+    }
+
+    let monad =
+        connect(NamedChain::MonadTestnet, &config.monad_rpc_urls, config.monad_max_tps, &config)?;
+    verify_tokens(&monad.provider, NamedChain::MonadTestnet).await?;
+
+    for account in &accounts {
+        let res = match config.nonce_manager {
+            NonceManagerKind::Strict => {
+                unwind_account::<StrictNonceManager>(&monad, repo.clone(), &config, account).await
+            }
+            NonceManagerKind::Pending => {
+                unwind_account::<PendingNonceManager>(&monad, repo.clone(), &config, account).await
+            }
+            NonceManagerKind::Cached => {
+                unwind_account::<CachedNonceManager>(&monad, repo.clone(), &config, account).await
+            }
+        };
+
+        if let Err(e) = res {
+            tracing::error!("Failed to unwind `{}`: {e}", account.address);
+        }
+    }
+
+    Ok(())
+}
+
+/// Asks which accounts to unwind, then unwinds them.
+pub async fn unwind_selected(repo: Arc<RepoImpls>, config: Arc<Config>) -> Result<()> {
+    let addresses = accounts::search(repo.clone(), AccountConditions::default())
+        .await?
+        .into_iter()
+        .map(|account| account.address)
+        .collect::<Vec<_>>();
+
+    let selection = MultiSelect::with_theme(&ColorfulTheme::default())
+        .with_prompt("Accounts to unwind (space to select):")
+        .items(&addresses)
+        .interact()?;
+
+    // an empty selection would unwind every account
+    if selection.is_empty() {
+        tracing::warn!("No accounts selected");
+        return Ok(());
+    }
+
+    let addresses = selection.into_iter().map(|i| addresses[i].clone()).collect();
+    unwind(repo, config, addresses).await
+}
+
+/// Runs every exit action of the registry that the account can run until it can't anymore.
+#[tracing::instrument(skip_all, fields(address = account.address))]
+async fn unwind_account<N>(
+    monad: &Connection<RootProvider>,
+    repo: Arc<RepoImpls>,
+    config: &Config,
+    account: &AccountModel,
+) -> Result<()>
+where
+    N: ClientNonceManager<RootProvider> + 'static,
+{
+    let client = EvmClient::<_, N>::new(
+        account.signer()?,
+        NamedChain::MonadTestnet.into(),
+        monad.provider.clone(),
+    )
+    .with_fee_policy(config.monad_fee_policy)
+    .with_rate_limiter(monad.rate_limiter.clone());
+
+    let http_client = account.http_client()?;
+
+    for action in registry::<_, N>().iter().filter(|action| action.kind() == "withdraw") {
+        for _ in 0..MAX_EXIT_STEPS {
+            // an exit may build on the one before it, e.g. a claim on a request
+            let history = transactions::history(repo.clone(), account.id).await?;
+
+            let Some(ctx) =
+                prepare(action.as_ref(), &client, &http_client, config, &history).await?
+            else {
+                break;
+            };
+
+            let outcome = run_action(repo.clone(), account.id, action.as_ref(), &ctx).await?;
+
+            if !outcome.is_some_and(|outcome| outcome.is_success()) {
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
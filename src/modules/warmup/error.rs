@@ -1,4 +1,5 @@
-use alloy::primitives::{Address, utils::UnitsError};
+use alloy::primitives::Address;
+use common::error::ErrorClass;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,9 +7,15 @@ pub enum WarmupError {
     #[error("no more actions left for `{0}`")]
     NoActionsLeft(Address),
 
-    #[error("no non-zero tokens at `{0}`")]
-    EmptyWallet(Address),
+    #[error("none of the remaining actions of `{0}` can run")]
+    NoAvailableActions(Address),
+}
 
-    #[error(transparent)]
-    FormatUnits(#[from] UnitsError),
+impl WarmupError {
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            WarmupError::NoActionsLeft(_) => ErrorClass::Permanent,
+            WarmupError::NoAvailableActions(_) => ErrorClass::NoAvailableActions,
+        }
+    }
 }
@@ -1,82 +1,178 @@
 use crate::Result;
 use alloy::{
     network::Ethereum,
+    primitives::U256,
     providers::{Provider, ProviderBuilder, RootProvider},
+    rpc::client::RpcClient,
 };
 use alloy_chains::NamedChain;
-use bridge::bridge;
 use common::{
-    config::Config,
-    onchain::client::{Client as EvmClient, StrictNonceManager},
+    config::{Config, NonceManagerKind, RpcEndpoint},
+    onchain::{
+        action::{Action, ActionContext, ActionRecord, prepare, registry},
+        client::{
+            CachedNonceManager, Client as EvmClient, ClientNonceManager, PendingNonceManager,
+            StrictNonceManager,
+        },
+        outcome::TxOutcome,
+        rate_limit::TxRateLimiter,
+        token::{Token, verify_tokens},
+        transport::FailoverTransport,
+    },
     utils::random::random_in_range,
 };
 use database::{
     entity::impls::{
         account::{AccountAction, AccountConditions},
         prelude::*,
+        transaction::{NewTransactionOptionsBuilder, TransactionStatus},
     },
-    repositories::RepoImpls,
-    use_cases::accounts,
+    repositories::{RepoImpls, create_scratch_repositories},
+    use_cases::{account_goals, accounts, transactions},
 };
 use error::WarmupError;
-use lending::deposit;
-use mint::mint;
-use std::{str::FromStr, sync::Arc, time::Duration};
-use swap::swap;
-use tokio::task::JoinSet;
-use url::Url;
-
-mod bridge;
+use rand::seq::SliceRandom;
+use shutdown::Shutdown;
+use std::{collections::HashSet, sync::Arc, time::Duration};
+use tokio::{sync::Semaphore, task::JoinSet};
+
 pub mod error;
-mod lending;
-mod mint;
-mod swap;
+mod report;
+mod shutdown;
+#[cfg(test)]
+mod tests;
+
+/// Runs the warmup for every account that hasn't reached its goal yet.
+///
+/// A dry run simulates every transaction instead of sending it and works on a scratch copy of
+/// the database, then prints what each account would have done.
+///
+/// At most `max_concurrent_accounts` accounts are processed at a time, the others wait for a free
+/// slot once their delay is over.
+///
+/// On SIGINT or SIGTERM no new actions are started and the transactions in flight are left to
+/// finish. The time every account had left to wait is saved, and the next warmup waits that out
+/// instead of staggering the account again.
+pub async fn run_warmup(repo: Arc<RepoImpls>, config: Arc<Config>, dry_run: bool) -> Result<()> {
+    let shutdown = Shutdown::listen();
+
+    // The inner state of the root provider.
+    // pub(crate) inner: Arc<RootProviderInner<N>>,
+    let monad =
+        connect(NamedChain::MonadTestnet, &config.monad_rpc_urls, config.monad_max_tps, &config)?;
+    let base = connect(NamedChain::Base, &config.base_rpc_urls, config.base_max_tps, &config)?;
+
+    // wrong decimals would scale every amount wrong, so nothing runs until the tokens match
+    verify_tokens(&monad.provider, NamedChain::MonadTestnet).await?;
+    verify_tokens(&base.provider, NamedChain::Base).await?;
+
+    warmup(repo, config, monad, base, dry_run, shutdown).await
+}
+
+/// Runs the warmup of [`run_warmup`] on the connections of both chains until every account is
+/// done, given up on or stopped by `shutdown`.
+async fn warmup(
+    repo: Arc<RepoImpls>,
+    config: Arc<Config>,
+    monad: Connection<RootProvider>,
+    base: Connection<RootProvider>,
+    dry_run: bool,
+    shutdown: Shutdown,
+) -> Result<()> {
+    let repo = match dry_run {
+        true => create_scratch_repositories(&repo).await?,
+        false => repo,
+    };
+    let last_transaction_id = transactions::last_id(repo.clone()).await?;
+
+    let workers = Arc::new(Semaphore::new(match config.max_concurrent_accounts {
+        0 => Semaphore::MAX_PERMITS,
+        max => max,
+    }));
 
-pub async fn run_warmup(repo: Arc<RepoImpls>, config: Arc<Config>) -> Result<()> {
     let spawn_task = |handles: &mut JoinSet<_>,
-                      monad_provider: RootProvider,
-                      base_provider: RootProvider,
+                      monad: Connection<RootProvider>,
+                      base: Connection<RootProvider>,
                       account: AccountModel,
-                      repo: Arc<_>,
-                      config: Arc<_>,
+                      repo: Arc<RepoImpls>,
+                      config: Arc<Config>,
                       delay: u64| {
+        let mut shutdown = shutdown.clone();
+        let workers = workers.clone();
+
         handles.spawn(async move {
             let id = account.id;
-            tokio::time::sleep(Duration::from_secs(delay)).await;
-            let res = process_account(monad_provider, base_provider, repo, config, account).await;
+
+            match wait(repo.clone(), id, delay, &mut shutdown).await {
+                Ok(true) => {}
+                Ok(false) => return (id, Ok(())),
+                Err(e) => return (id, Err(e)),
+            }
+
+            // the delay is over, so an account that's still waiting for a free slot when the
+            // warmup stops starts right away on the next run
+            let _permit = tokio::select! {
+                permit = workers.acquire_owned() => permit.expect("semaphore to stay open"),
+                _ = shutdown.requested() => {
+                    let res = accounts::set_resume_delay(repo, id, Some(0)).await;
+                    return (id, res.map(|_| ()).map_err(Into::into));
+                }
+            };
+
+            let res = match config.nonce_manager {
+                NonceManagerKind::Strict => {
+                    process_account::<_, StrictNonceManager>(
+                        monad, base, repo, config, account, dry_run, shutdown,
+                    )
+                    .await
+                }
+                NonceManagerKind::Pending => {
+                    process_account::<_, PendingNonceManager>(
+                        monad, base, repo, config, account, dry_run, shutdown,
+                    )
+                    .await
+                }
+                NonceManagerKind::Cached => {
+                    process_account::<_, CachedNonceManager>(
+                        monad, base, repo, config, account, dry_run, shutdown,
+                    )
+                    .await
+                }
+            };
 
             (id, res)
         })
     };
 
-    // The inner state of the root provider.
-    // pub(crate) inner: Arc<RootProviderInner<N>>,
-    let monad_provider = ProviderBuilder::new()
-        .disable_recommended_fillers()
-        .network::<Ethereum>()
-        .with_chain(NamedChain::MonadTestnet)
-        .on_http(Url::from_str(&config.monad_rpc_url)?);
-
-    let base_provider = ProviderBuilder::new()
-        .disable_recommended_fillers()
-        .network::<Ethereum>()
-        .with_chain(NamedChain::Base)
-        .on_http(Url::from_str(&config.base_rpc_url)?);
-
     let accounts = accounts::search(
         repo.clone(),
-        AccountConditions { goal_reached: Some(false), ..Default::default() },
+        AccountConditions { goal_reached: Some(false), active: Some(true), ..Default::default() },
     )
     .await?;
 
     let mut handles = JoinSet::new();
+    let mut staggered = 0;
+
+    for account in accounts {
+        let delay = match (dry_run, account.resume_delay) {
+            (true, _) => 0,
+            (false, Some(resume_delay)) => resume_delay as u64,
+            (false, None) => {
+                let delay = random_in_range(config.thread_delay) * staggered;
+                staggered += 1;
+                delay
+            }
+        };
+
+        // the saved delay is used up, a shutdown while it's waited out saves what's left again
+        if account.resume_delay.is_some() {
+            accounts::set_resume_delay(repo.clone(), account.id, None).await?;
+        }
 
-    for (i, account) in accounts.into_iter().enumerate() {
-        let delay = random_in_range(config.thread_delay) * i as u64;
         spawn_task(
             &mut handles,
-            monad_provider.clone(),
-            base_provider.clone(),
+            monad.clone(),
+            base.clone(),
             account,
             repo.clone(),
             config.clone(),
@@ -89,100 +185,361 @@ pub async fn run_warmup(repo: Arc<RepoImpls>, config: Arc<Config>) -> Result<()>
 
         if let Err(e) = result {
             match e {
-                crate::Error::Warmup(warmup_error) => {
-                    // the wallet is either empty or has no more actions left
-                    if let WarmupError::EmptyWallet(a) = warmup_error {
-                        tracing::warn!("Wallet {a} has no non-zero balance tokens")
-                    }
+                crate::Error::Warmup(WarmupError::NoActionsLeft(_)) => {
                     accounts::deactivate_account_by_id(repo.clone(), id).await?;
                 }
+                _ if dry_run => {
+                    tracing::error!("Thread stopped with error: {e}, skipping the account");
+                }
                 _ => {
-                    tracing::error!(
-                        "Thread stopped with error: {e}, restarting a thread in {} seconds",
-                        config.restart_thread_delay
-                    );
+                    let Some(delay) = count_failure(repo.clone(), &config, id, &e).await? else {
+                        continue;
+                    };
 
                     let account = accounts::search_account_by_id(repo.clone(), id).await?;
 
                     spawn_task(
                         &mut handles,
-                        monad_provider.clone(),
-                        base_provider.clone(),
+                        monad.clone(),
+                        base.clone(),
                         account,
                         repo.clone(),
                         config.clone(),
-                        config.restart_thread_delay,
+                        delay,
                     );
                 }
             }
         }
     }
 
+    if shutdown.is_requested() {
+        tracing::warn!("Warmup stopped, run it again to resume where it left off");
+    }
+
+    if dry_run {
+        report::print(repo, last_transaction_id).await?;
+    }
+
     Ok(())
 }
 
-/// Continuously processes an account by executing its available actions.
+/// Counts the failure of an account and decides whether it tries again. The account is given up
+/// on once it failed more times in a row than the retries of the class of `e` allow.
+///
+/// Returns the seconds to wait before the account starts over, or `None` if it was given up on.
+async fn count_failure(
+    repo: Arc<RepoImpls>,
+    config: &Config,
+    account_id: i32,
+    e: &crate::Error,
+) -> Result<Option<u64>> {
+    let class = e.class();
+    let max_retries = config.retry_policy.max_retries(class);
+    let failures = accounts::record_failure(repo.clone(), account_id).await? as u32;
+
+    if failures > max_retries {
+        tracing::error!(
+            "Thread stopped with {class} error: {e}, giving up on the account after {failures} \
+             failures in a row"
+        );
+        let reason = format!("{failures} {class} failures in a row: {e}");
+        accounts::give_up(repo, account_id, reason).await?;
+        return Ok(None);
+    }
+
+    let delay = config.retry_delay(failures);
+    tracing::error!(
+        "Thread stopped with {class} error: {e}, restarting a thread in {delay} seconds (retry \
+         {failures} of {max_retries})"
+    );
+
+    Ok(Some(delay))
+}
+
+/// Waits `delay` seconds before the next step of an account. If a shutdown cuts it short, the
+/// time left is saved for the next warmup and `false` is returned.
+async fn wait(
+    repo: Arc<RepoImpls>,
+    account_id: i32,
+    delay: u64,
+    shutdown: &mut Shutdown,
+) -> Result<bool> {
+    match shutdown.sleep(Duration::from_secs(delay)).await {
+        None => Ok(true),
+        Some(left) => {
+            accounts::set_resume_delay(repo, account_id, Some(left.as_secs() as i32)).await?;
+            Ok(false)
+        }
+    }
+}
+
+/// The provider of a chain along with the limiter shared by every client on it.
+#[derive(Clone)]
+pub(crate) struct Connection<P> {
+    pub(crate) provider: P,
+    pub(crate) rate_limiter: Option<TxRateLimiter>,
+}
+
+pub(crate) fn connect(
+    chain: NamedChain,
+    endpoints: &[RpcEndpoint],
+    max_tps: Option<f64>,
+    config: &Config,
+) -> Result<Connection<RootProvider>> {
+    Ok(Connection {
+        provider: provider(chain, endpoints, config)?,
+        rate_limiter: max_tps.map(TxRateLimiter::new),
+    })
+}
+
+/// Builds a provider that fails over between the RPC endpoints of a chain.
+fn provider(chain: NamedChain, endpoints: &[RpcEndpoint], config: &Config) -> Result<RootProvider> {
+    let mut transport =
+        FailoverTransport::new(endpoints, Duration::from_secs(config.rpc_cooldown))?;
+    if let Some(interval) = config.rpc_health_check_interval() {
+        transport = transport.with_health_checks(interval);
+    }
+
+    let provider = ProviderBuilder::new()
+        .disable_recommended_fillers()
+        .network::<Ethereum>()
+        .with_chain(chain)
+        .on_client(RpcClient::new(transport, false));
+
+    Ok(provider)
+}
+
+/// Continuously processes an account by executing a random action from the [`registry`] that
+/// works towards one of its unfinished goals. Until the account has bridged, only the bridge is
+/// picked.
+///
+/// An action is only picked if the native balance covers what it sends and its gas while keeping
+/// the gas reserve of the chain, otherwise another one is tried.
 ///
 /// # Errors
 ///
 /// Propagates errors from underlying operations. A returned `WarmupError::NoActionsLeft`
-/// means that the account has no more available actions.
+/// means that the account has no more goals left, `WarmupError::NoAvailableActions` that none
+/// of the actions for its goals can run. Returns `Ok` once a shutdown is requested, or in a dry
+/// run once every action of its goals was simulated.
 #[tracing::instrument(skip_all, fields(address))]
-async fn process_account<P>(
-    monad_provider: P,
-    base_provider: P,
+async fn process_account<P, N>(
+    monad: Connection<P>,
+    base: Connection<P>,
     repo: Arc<RepoImpls>,
     config: Arc<Config>,
     account: AccountModel,
+    dry_run: bool,
+    mut shutdown: Shutdown,
 ) -> Result<()>
 where
-    P: Provider<Ethereum>,
+    P: Provider<Ethereum> + 'static,
+    N: ClientNonceManager<P> + 'static,
 {
-    let signer = account.signer();
-    tracing::Span::current().record("address", signer.address().to_string());
+    tracing::Span::current().record("address", account.address.as_str());
+    let signer = account.signer()?;
 
-    let monad_client = EvmClient::<_, StrictNonceManager>::new(
-        signer.clone(),
-        NamedChain::MonadTestnet.into(),
-        monad_provider,
-    );
+    let monad_client =
+        EvmClient::<_, N>::new(signer.clone(), NamedChain::MonadTestnet.into(), monad.provider)
+            .with_fee_policy(config.monad_fee_policy)
+            .with_rate_limiter(monad.rate_limiter)
+            .with_simulation(dry_run);
+
+    let base_client = EvmClient::<_, N>::new(signer, NamedChain::Base.into(), base.provider)
+        .with_fee_policy(config.base_fee_policy)
+        .with_rate_limiter(base.rate_limiter)
+        .with_simulation(dry_run);
 
-    let base_client =
-        EvmClient::<_, StrictNonceManager>::new(signer, NamedChain::Base.into(), base_provider);
+    let http_client = account.http_client()?;
+    let actions = registry::<P, N>();
+
+    let account_id = account.id;
+
+    // nothing a dry run simulates changes the chain state, so it tries every action just once
+    let mut simulated = HashSet::new();
 
     loop {
-        let account = accounts::search_account_by_id(repo.clone(), account.id).await?;
+        if shutdown.is_requested() {
+            return Ok(());
+        }
 
-        let action = account
-            .random_available_action()
-            .ok_or_else(|| WarmupError::NoActionsLeft(account.address()))?;
+        let account = accounts::search_account_by_id(repo.clone(), account_id).await?;
+        let goals = account_goals::search_by_account_id(repo.clone(), account.id).await?;
+        let history = transactions::history(repo.clone(), account.id).await?;
 
-        match action {
-            AccountAction::Swap(dex) => {
-                // true -> update_swap_count
-                if swap(dex, &account, &monad_client, config.clone()).await? {
-                    accounts::update_swap_count(repo.clone(), dex, &account).await?;
-                }
-            }
-            AccountAction::Lending(lending) => {
-                if deposit(lending, &monad_client, config.clone()).await? {
-                    accounts::update_deposit_count(repo.clone(), lending, &account).await?;
-                }
+        let mut candidates = actions
+            .iter()
+            .filter(|action| match account.bridge_goal {
+                false => action.kind() == AccountAction::Bridge.kind(),
+                true => goals.iter().any(|goal| {
+                    !goal.is_done() &&
+                        goal.action == action.kind() &&
+                        goal.protocol == action.protocol()
+                }),
+            })
+            .collect::<Vec<_>>();
+
+        if candidates.is_empty() {
+            return Err(WarmupError::NoActionsLeft(account.address()).into());
+        }
+
+        candidates.retain(|action| !simulated.contains(&(action.kind(), action.protocol())));
+        if candidates.is_empty() {
+            return Ok(());
+        }
+
+        candidates.shuffle(&mut rand::rng());
+
+        let mut picked = None;
+        for action in candidates {
+            let client = match action.chain() {
+                NamedChain::Base => &base_client,
+                _ => &monad_client,
+            };
+
+            let ctx = prepare(action.as_ref(), client, &http_client, &config, &history).await?;
+
+            if let Some(ctx) = ctx {
+                picked = Some((action, ctx));
+                break;
             }
-            AccountAction::Mint(nft) => {
-                if mint(nft, &account, &monad_client).await? {
-                    accounts::update_mint_count(repo.clone(), nft, account).await?;
-                }
+        }
+
+        let (action, ctx) =
+            picked.ok_or_else(|| WarmupError::NoAvailableActions(account.address()))?;
+
+        let res = run_action(repo.clone(), account.id, action.as_ref(), &ctx).await;
+
+        if dry_run {
+            simulated.insert((action.kind(), action.protocol()));
+        }
+
+        // only a confirmed transaction, or a successful simulation in a dry run, advances the
+        // counters
+        if res?.is_some_and(|o| o.is_success()) {
+            if account.failure_count > 0 {
+                accounts::reset_failures(repo.clone(), account_id).await?;
             }
-            AccountAction::Bridge => {
-                if bridge(&base_client, config.clone()).await? {
+
+            match action.kind() == AccountAction::Bridge.kind() {
+                true => {
                     accounts::update_bridge_goal(repo.clone(), account).await?;
                 }
+                false => {
+                    account_goals::increment(
+                        repo.clone(),
+                        account.id,
+                        action.kind(),
+                        &action.protocol(),
+                    )
+                    .await?;
+                }
             }
         }
 
-        let delay = random_in_range(config.action_delay) as u64;
-        tracing::info!("Sleeping for {delay} seconds");
-        tokio::time::sleep(Duration::from_secs(delay)).await;
+        if !dry_run {
+            let delay = random_in_range(config.action_delay) as u64;
+            tracing::info!("Sleeping for {delay} seconds");
+
+            if !wait(repo.clone(), account_id, delay, &mut shutdown).await? {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Runs the action and writes it to the transaction history, after the transactions it sent on
+/// the way, e.g. an approval.
+pub(crate) async fn run_action<P, N>(
+    repo: Arc<RepoImpls>,
+    account_id: i32,
+    action: &dyn Action<P, N>,
+    ctx: &ActionContext<'_, P, N>,
+) -> Result<Option<TxOutcome>>
+where
+    P: Provider<Ethereum>,
+    N: ClientNonceManager<P>,
+{
+    let mut action_record = ActionRecord::default();
+    let res = action.execute(ctx, &mut action_record).await.map_err(Into::into);
+    let chain_id = ctx.client.chain.id();
+
+    for sent in action_record.sent {
+        let mut record = new_transaction(
+            account_id,
+            chain_id,
+            sent.kind,
+            &sent.protocol,
+            sent.token_in,
+            sent.token_out,
+            sent.amount_in,
+        );
+        record_transaction(repo.clone(), &mut record, &Ok(Some(sent.outcome))).await;
+    }
+
+    let mut record = new_transaction(
+        account_id,
+        chain_id,
+        action.kind(),
+        &action.protocol(),
+        action_record.token_in,
+        action_record.token_out,
+        action_record.amount_in,
+    );
+    record_transaction(repo, &mut record, &res).await;
+
+    res
+}
+
+/// The row of a transaction in the history, without its outcome.
+fn new_transaction(
+    account_id: i32,
+    chain_id: u64,
+    kind: &str,
+    protocol: &str,
+    token_in: Option<Token>,
+    token_out: Option<Token>,
+    amount_in: Option<U256>,
+) -> NewTransactionOptionsBuilder {
+    let mut record = NewTransactionOptionsBuilder::default();
+    record.account_id(account_id).chain_id(chain_id).action(kind).protocol(protocol);
+    if let Some(token_in) = token_in {
+        record.token_in(token_in.ticker());
+    }
+    if let Some(token_out) = token_out {
+        record.token_out(token_out.ticker());
+    }
+    if let Some(amount_in) = amount_in {
+        record.amount_in(amount_in.to_string());
+    }
+
+    record
+}
+
+/// Writes the action to the transaction history. Actions that didn't send anything are skipped.
+///
+/// A failure to write is only logged, so that it doesn't interrupt the warmup.
+async fn record_transaction(
+    repo: Arc<RepoImpls>,
+    record: &mut NewTransactionOptionsBuilder,
+    res: &Result<Option<TxOutcome>>,
+) {
+    match res {
+        Ok(Some(outcome)) => {
+            record.outcome(outcome);
+        }
+        Ok(None) => return,
+        Err(e) => {
+            record.status(TransactionStatus::Failed).error(e.to_string());
+        }
+    }
+
+    let res = match record.build() {
+        Ok(opts) => transactions::add(repo, TransactionActiveModel::new(opts)).await,
+        Err(e) => Err(e.into()),
+    };
+
+    if let Err(e) = res {
+        tracing::error!("Failed to record the transaction: {e}");
     }
 }
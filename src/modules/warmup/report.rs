@@ -0,0 +1,96 @@
+use alloy::primitives::{U256, utils::format_units};
+use alloy_chains::NamedChain;
+use common::onchain::token::Token;
+use database::{
+    entity::impls::{
+        account::AccountConditions, prelude::TransactionModel, transaction::TransactionConditions,
+    },
+    repositories::RepoImpls,
+    use_cases::{accounts, transactions},
+};
+use std::{collections::BTreeMap, sync::Arc};
+
+use crate::Result;
+
+fn native_token(chain_id: i64) -> Token {
+    match chain_id == NamedChain::Base as i64 {
+        true => Token::ETH,
+        false => Token::MON,
+    }
+}
+
+fn format_amount(amount: U256, token: Token) -> String {
+    let amount = format_units(amount, token.decimals()).unwrap_or_else(|_| amount.to_string());
+    format!("{amount} {token}")
+}
+
+fn describe(tx: &TransactionModel) -> String {
+    let mut line = format!("{} {}", tx.action, tx.protocol);
+
+    let token_in = tx.token_in.as_deref().and_then(Token::from_ticker);
+    let amount_in = tx.amount_in.as_deref().and_then(|a| a.parse::<U256>().ok());
+
+    if let (Some(token_in), Some(amount_in)) = (token_in, amount_in) {
+        line.push_str(&format!(" {}", format_amount(amount_in, token_in)));
+    }
+
+    if let Some(token_out) = &tx.token_out {
+        line.push_str(&format!(" -> {token_out}"));
+    }
+
+    line.push_str(&format!(": {}", tx.status));
+
+    if let Some(error) = &tx.error {
+        line.push_str(&format!(" ({error})"));
+    }
+
+    line
+}
+
+/// Prints what each account did during a dry run along with the estimated gas spend per chain.
+///
+/// The scratch database is a copy of the real one, so only the transactions added after the one
+/// with id `after_id` belong to the run.
+pub async fn print(repo: Arc<RepoImpls>, after_id: i32) -> Result<()> {
+    let accounts = accounts::search(repo.clone(), AccountConditions::default()).await?;
+    let mut total_gas_cost = BTreeMap::<i64, U256>::new();
+
+    println!("Dry run report:");
+
+    for account in accounts {
+        let txs = transactions::search(
+            repo.clone(),
+            TransactionConditions {
+                account_id: Some(account.id),
+                after_id: Some(after_id),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        if txs.is_empty() {
+            continue;
+        }
+
+        println!("{}", account.address);
+
+        for tx in txs {
+            let gas_cost =
+                tx.gas_cost.as_deref().and_then(|c| c.parse::<U256>().ok()).unwrap_or_default();
+            *total_gas_cost.entry(tx.chain_id).or_default() += gas_cost;
+
+            println!(
+                "  {}, gas: {}",
+                describe(&tx),
+                format_amount(gas_cost, native_token(tx.chain_id))
+            );
+        }
+    }
+
+    println!("Estimated gas spend:");
+    for (chain_id, gas_cost) in total_gas_cost {
+        println!("  {}", format_amount(gas_cost, native_token(chain_id)));
+    }
+
+    Ok(())
+}
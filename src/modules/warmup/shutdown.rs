@@ -0,0 +1,92 @@
+use std::{
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+use tokio::sync::watch;
+
+static SHUTDOWN: OnceLock<watch::Sender<bool>> = OnceLock::new();
+
+/// Whether a SIGINT or SIGTERM asked the warmup to stop.
+///
+/// The first signal during a warmup only stops new actions from being scheduled, so that the
+/// transactions in flight can finish. A second one, or one outside of a warmup, exits right away.
+#[derive(Clone)]
+pub struct Shutdown(watch::Receiver<bool>);
+
+impl Shutdown {
+    /// Starts listening for the signals on the first call and resets the request of a previous
+    /// warmup.
+    pub fn listen() -> Self {
+        let sender = SHUTDOWN.get_or_init(|| {
+            tokio::spawn(handle_signals());
+            watch::channel(false).0
+        });
+
+        sender.send_replace(false);
+        Self(sender.subscribe())
+    }
+
+    /// A shutdown that is requested through the returned sender rather than a signal.
+    #[cfg(test)]
+    pub fn channel() -> (watch::Sender<bool>, Self) {
+        let (sender, receiver) = watch::channel(false);
+        (sender, Self(receiver))
+    }
+
+    pub fn is_requested(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Resolves once a shutdown is requested.
+    pub async fn requested(&mut self) {
+        let _ = self.0.wait_for(|requested| *requested).await;
+    }
+
+    /// Sleeps for `duration` unless a shutdown is requested first. Returns the time that was left
+    /// if it was cut short.
+    pub async fn sleep(&mut self, duration: Duration) -> Option<Duration> {
+        let deadline = Instant::now() + duration;
+
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => None,
+            _ = self.requested() => {
+                Some(deadline.saturating_duration_since(Instant::now()))
+            }
+        }
+    }
+}
+
+async fn handle_signals() {
+    loop {
+        wait_for_signal().await;
+
+        let Some(sender) = SHUTDOWN.get() else { continue };
+
+        if sender.receiver_count() == 0 || *sender.borrow() {
+            std::process::exit(130);
+        }
+
+        tracing::warn!(
+            "Shutting down once the transactions in flight are done, press Ctrl-C again to exit \
+             right away"
+        );
+        sender.send_replace(true);
+    }
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut terminate = signal(SignalKind::terminate()).expect("SIGTERM handler to be installed");
+
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminate.recv() => {}
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
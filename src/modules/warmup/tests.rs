@@ -0,0 +1,522 @@
+use super::*;
+use alloy::{
+    hex,
+    primitives::{Address, B256, Bytes, TxHash, keccak256},
+    providers::{MULTICALL3_ADDRESS, bindings::IMulticall3::aggregateCall},
+    signers::local::PrivateKeySigner,
+    sol_types::SolCall,
+};
+use common::onchain::{
+    action::{ActionError, PastTransaction},
+    dapps::{bean::BeanSwap, kinza::KinzaWithdraw},
+};
+use database::{
+    crypto,
+    entity::impls::{account::NewActiveModelOptionsBuilder, transaction::TransactionConditions},
+    repositories::create_repositories_at,
+};
+use serde_json::{Value, json};
+use std::{path::PathBuf, sync::Mutex};
+use tokio::time::Instant;
+use wiremock::{Mock, MockServer, Request, ResponseTemplate, matchers::method};
+
+/// Anvil's first two dev accounts, as private key and address.
+const ANVIL_KEYS: [(&str, &str); 2] = [
+    (
+        "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+        "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266",
+    ),
+    (
+        "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d",
+        "0x70997970C51812dc3A010C7d01b50e0d17dc79C8",
+    ),
+];
+
+/// The Kinza deposit whose receipt mints [`KTOKEN`].
+const DEPOSIT: B256 = B256::repeat_byte(0xd0);
+const KTOKEN: Address = Address::repeat_byte(0x4b);
+
+/// A node that mines every transaction it gets right away, keeping the raw transactions. Every
+/// account holds 10 MON, one WMON and one [`KTOKEN`], none of them approved for anyone, and the
+/// receipt of [`DEPOSIT`] mints [`KTOKEN`] to the first of [`ANVIL_KEYS`].
+struct MockNode {
+    server: MockServer,
+    sent: Arc<Mutex<Vec<Bytes>>>,
+}
+
+impl MockNode {
+    async fn start() -> Self {
+        Self::start_with(|| {}).await
+    }
+
+    /// Starts a node that calls `on_send` whenever it gets a transaction, before it's mined.
+    async fn start_with(on_send: impl Fn() + Send + Sync + 'static) -> Self {
+        let server = MockServer::start().await;
+        let sent = Arc::new(Mutex::new(vec![]));
+
+        let received = sent.clone();
+        Mock::given(method("POST"))
+            .respond_with(move |request: &Request| {
+                let request: Value = serde_json::from_slice(&request.body).unwrap();
+                let params = &request["params"];
+
+                let result = match request["method"].as_str().unwrap() {
+                    "eth_chainId" => json!(format!("{:#x}", NamedChain::MonadTestnet as u64)),
+                    "eth_blockNumber" => json!("0x1"),
+                    "eth_gasPrice" => json!("0x3b9aca00"),
+                    "eth_feeHistory" => json!({
+                        "oldestBlock": "0x1",
+                        "baseFeePerGas": ["0x3b9aca00", "0x3b9aca00"],
+                        "gasUsedRatio": [0.5],
+                        "reward": [["0x1"]],
+                    }),
+                    "eth_getBalance" => json!(format!("{:#x}", U256::from(10u128.pow(19)))),
+                    "eth_getTransactionCount" => json!("0x0"),
+                    "eth_estimateGas" => json!("0x5208"),
+                    "eth_call" => {
+                        let tx = &params[0];
+                        let to = serde_json::from_value(tx["to"].clone()).unwrap();
+                        let input = tx["input"].clone();
+                        let input: Bytes = serde_json::from_value(if input.is_null() {
+                            tx["data"].clone()
+                        } else {
+                            input
+                        })
+                        .unwrap();
+                        json!(call(to, &input))
+                    }
+                    "eth_sendRawTransaction" => {
+                        let raw: Bytes = serde_json::from_value(params[0].clone()).unwrap();
+                        let hash = keccak256(&raw);
+                        received.lock().unwrap().push(raw);
+                        on_send();
+                        json!(hash)
+                    }
+                    "eth_getTransactionReceipt" => {
+                        receipt(serde_json::from_value(params[0].clone()).unwrap())
+                    }
+                    _ => Value::Null,
+                };
+
+                ResponseTemplate::new(200).set_body_json(
+                    json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }),
+                )
+            })
+            .mount(&server)
+            .await;
+
+        Self { server, sent }
+    }
+
+    fn connection(&self) -> Connection<RootProvider> {
+        Connection {
+            provider: ProviderBuilder::new()
+                .disable_recommended_fillers()
+                .network::<Ethereum>()
+                .on_http(self.server.uri().parse().unwrap()),
+            rate_limiter: None,
+        }
+    }
+
+    /// The number of transactions sent to the node.
+    fn sent(&self) -> usize {
+        self.sent.lock().unwrap().len()
+    }
+}
+
+/// The result of a call: `allowance` is zero, `balanceOf` one token for WMON and [`KTOKEN`] and
+/// zero for the rest, and anything else one token. A multicall gets the result of every call.
+fn call(to: Address, input: &[u8]) -> Bytes {
+    if to == MULTICALL3_ADDRESS {
+        let calls = aggregateCall::abi_decode(input, true).unwrap().calls;
+        let return_data: Vec<_> = calls.iter().map(|c| call(c.target, &c.callData)).collect();
+        return aggregateCall::abi_encode_returns(&(U256::from(1), return_data)).into();
+    }
+
+    let word = match input[..4] {
+        [0xdd, 0x62, 0xed, 0x3e] => U256::ZERO,
+        [0x70, 0xa0, 0x82, 0x31] if to != Token::WMON.address() && to != KTOKEN => U256::ZERO,
+        _ => U256::from(10u128.pow(18)),
+    };
+    Bytes::from(word.to_be_bytes::<32>())
+}
+
+/// A successful receipt of the transaction, with the mint of the kToken for [`DEPOSIT`].
+fn receipt(hash: B256) -> Value {
+    let logs = match hash == DEPOSIT {
+        true => json!([{
+            "address": KTOKEN,
+            "topics": [
+                keccak256("Transfer(address,address,uint256)"),
+                B256::ZERO,
+                B256::left_padding_from(ANVIL_KEYS[0].1.parse::<Address>().unwrap().as_slice()),
+            ],
+            "data": Bytes::from(U256::from(10u128.pow(18)).to_be_bytes::<32>()),
+            "blockNumber": "0x1",
+            "blockHash": B256::repeat_byte(0x11),
+            "transactionHash": hash,
+            "transactionIndex": "0x0",
+            "logIndex": "0x0",
+            "removed": false,
+        }]),
+        false => json!([]),
+    };
+
+    json!({
+        "type": "0x2",
+        "status": "0x1",
+        "cumulativeGasUsed": "0x5208",
+        "logs": logs,
+        "logsBloom": hex::encode_prefixed([0u8; 256]),
+        "transactionHash": hash,
+        "transactionIndex": "0x0",
+        "blockHash": B256::repeat_byte(0x11),
+        "blockNumber": "0x1",
+        "gasUsed": "0x5208",
+        "effectiveGasPrice": "0x3b9aca00",
+        "from": Address::ZERO,
+        "to": Address::ZERO,
+        "contractAddress": null,
+    })
+}
+
+/// A migrated database in a file of its own, removed once the test is done.
+struct TestDb {
+    repo: Arc<RepoImpls>,
+    path: PathBuf,
+}
+
+impl TestDb {
+    async fn new(name: &str) -> Self {
+        crypto::unlock("test".to_string());
+
+        let path = std::env::temp_dir()
+            .join(format!("monad-pulse-{}-warmup-{name}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        Self { repo: create_repositories_at(&path).await.unwrap(), path }
+    }
+
+    /// Adds an account that has bridged and has to wrap MON once.
+    async fn add_account(&self, pk: &str, address: &str, config: &Config) -> i32 {
+        let opts = NewActiveModelOptionsBuilder::default()
+            .pk(crypto::encrypt(pk).unwrap())
+            .proxy(None)
+            .address(address.to_string())
+            .bridge_goal(true)
+            .build()
+            .unwrap();
+
+        let id = accounts::add(self.repo.clone(), AccountActiveModel::new(opts)).await.unwrap();
+        account_goals::roll_targets(self.repo.clone(), id, config, true).await.unwrap();
+        id
+    }
+}
+
+impl Drop for TestDb {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// The shipped config with a single wrap as the goal of every account and no delays between the
+/// accounts and their actions.
+fn config() -> Config {
+    let mut config = Config::parse(include_str!("../../../data/config.toml")).unwrap();
+    config.wmon_wrap_count = [1, 1];
+    config.thread_delay = [0, 0];
+    config.action_delay = [0, 0];
+    config
+}
+
+/// A connection to a node that is never reached.
+fn unreachable() -> Connection<RootProvider> {
+    Connection {
+        provider: ProviderBuilder::new()
+            .disable_recommended_fillers()
+            .network::<Ethereum>()
+            .on_http("http://127.0.0.1:1".parse().unwrap()),
+        rate_limiter: None,
+    }
+}
+
+fn revert() -> crate::Error {
+    common::Error::from(ActionError::UnwrapFailed(TxHash::ZERO)).into()
+}
+
+fn permanent() -> crate::Error {
+    common::Error::from(ActionError::NoSwapTarget(common::onchain::token::Token::MON)).into()
+}
+
+#[tokio::test]
+async fn backs_off_exponentially_up_to_the_longest_delay() {
+    let db = TestDb::new("backoff").await;
+    let mut config = config();
+    config.restart_thread_delay = 10;
+    config.retry_policy.max_delay = 45;
+    config.retry_policy.revert = 5;
+    let id = db.add_account(ANVIL_KEYS[0].0, ANVIL_KEYS[0].1, &config).await;
+
+    let mut delays = vec![];
+    for _ in 0..5 {
+        delays.push(count_failure(db.repo.clone(), &config, id, &revert()).await.unwrap());
+    }
+    assert_eq!(delays, [Some(10), Some(20), Some(40), Some(45), Some(45)]);
+
+    let account = accounts::search_account_by_id(db.repo.clone(), id).await.unwrap();
+    assert_eq!(account.failure_count, 5);
+    assert!(account.active);
+}
+
+#[tokio::test]
+async fn gives_up_once_the_retries_of_the_class_are_used_up() {
+    let db = TestDb::new("class-limits").await;
+    let mut config = config();
+    config.retry_policy.revert = 2;
+    config.retry_policy.permanent = 0;
+    let reverting = db.add_account(ANVIL_KEYS[0].0, ANVIL_KEYS[0].1, &config).await;
+    let failing = db.add_account(ANVIL_KEYS[1].0, ANVIL_KEYS[1].1, &config).await;
+
+    for _ in 0..2 {
+        assert!(
+            count_failure(db.repo.clone(), &config, reverting, &revert()).await.unwrap().is_some()
+        );
+    }
+    assert_eq!(count_failure(db.repo.clone(), &config, reverting, &revert()).await.unwrap(), None);
+    assert_eq!(count_failure(db.repo.clone(), &config, failing, &permanent()).await.unwrap(), None);
+
+    let account = accounts::search_account_by_id(db.repo.clone(), reverting).await.unwrap();
+    assert!(!account.active);
+    assert_eq!(account.failure_count, 3);
+    assert!(account.inactive_reason.unwrap().starts_with("3 revert failures in a row"));
+
+    let account = accounts::search_account_by_id(db.repo.clone(), failing).await.unwrap();
+    assert!(!account.active);
+    assert!(account.inactive_reason.unwrap().starts_with("1 permanent failures in a row"));
+}
+
+#[tokio::test]
+async fn restarts_a_failing_account_after_the_delay_until_it_is_given_up_on() {
+    let db = TestDb::new("retry-loop").await;
+    let mut config = config();
+    config.restart_thread_delay = 1;
+    config.retry_policy.permanent = 2;
+
+    // the key can't be read, so the account fails before it reaches the node
+    let opts = NewActiveModelOptionsBuilder::default()
+        .pk("not a key".to_string())
+        .proxy(None)
+        .address(ANVIL_KEYS[0].1.to_string())
+        .bridge_goal(true)
+        .build()
+        .unwrap();
+    let id = accounts::add(db.repo.clone(), AccountActiveModel::new(opts)).await.unwrap();
+    account_goals::roll_targets(db.repo.clone(), id, &config, true).await.unwrap();
+
+    let (_sender, shutdown) = Shutdown::channel();
+    let started = Instant::now();
+    warmup(db.repo.clone(), Arc::new(config), unreachable(), unreachable(), false, shutdown)
+        .await
+        .unwrap();
+
+    // one second after the first failure, two after the second
+    assert!(started.elapsed() >= Duration::from_secs(3));
+
+    let account = accounts::search_account_by_id(db.repo.clone(), id).await.unwrap();
+    assert!(!account.active);
+    assert_eq!(account.failure_count, 3);
+    assert!(account.inactive_reason.unwrap().starts_with("3 permanent failures in a row"));
+}
+
+#[tokio::test]
+async fn records_the_approval_before_the_transaction_it_is_for() {
+    let db = TestDb::new("approvals").await;
+    let node = MockNode::start().await;
+    let config = config();
+    let id = db.add_account(ANVIL_KEYS[0].0, ANVIL_KEYS[0].1, &config).await;
+    let account = accounts::search_account_by_id(db.repo.clone(), id).await.unwrap();
+
+    let signer = ANVIL_KEYS[0].0.parse::<PrivateKeySigner>().unwrap();
+    let client = EvmClient::<_, StrictNonceManager>::new(
+        signer,
+        NamedChain::MonadTestnet.into(),
+        node.connection().provider,
+    );
+    let http_client = account.http_client().unwrap();
+    let history = [PastTransaction {
+        action: "lending".to_string(),
+        protocol: "KINZA".to_string(),
+        hash: DEPOSIT,
+    }];
+    let ctx = ActionContext {
+        client: &client,
+        http_client: &http_client,
+        config: &config,
+        reserve: U256::ZERO,
+        history: &history,
+    };
+
+    let outcome = run_action(db.repo.clone(), id, &KinzaWithdraw, &ctx).await.unwrap().unwrap();
+    assert!(outcome.is_success());
+    assert_eq!(node.sent(), 2);
+
+    let txs = transactions::search(
+        db.repo.clone(),
+        TransactionConditions { account_id: Some(id), ..Default::default() },
+    )
+    .await
+    .unwrap();
+    let rows = txs
+        .iter()
+        .map(|tx| (tx.action.as_str(), tx.protocol.as_str(), tx.status.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(rows, [("approve", "KINZA", "success"), ("withdraw", "KINZA", "success")]);
+    assert_ne!(txs[0].tx_hash, txs[1].tx_hash);
+    assert_eq!(txs[0].amount_in.as_deref(), Some("1000000000000000000"));
+}
+
+#[tokio::test]
+async fn records_the_unwrap_before_the_swap_it_is_for() {
+    let db = TestDb::new("unwraps").await;
+    let node = MockNode::start().await;
+    let config = config();
+    let id = db.add_account(ANVIL_KEYS[0].0, ANVIL_KEYS[0].1, &config).await;
+    let account = accounts::search_account_by_id(db.repo.clone(), id).await.unwrap();
+
+    let signer = ANVIL_KEYS[0].0.parse::<PrivateKeySigner>().unwrap();
+    let client = EvmClient::<_, StrictNonceManager>::new(
+        signer,
+        NamedChain::MonadTestnet.into(),
+        node.connection().provider,
+    );
+    let http_client = account.http_client().unwrap();
+    // all of the MON is reserved, so WMON is the only token to swap
+    let ctx = ActionContext {
+        client: &client,
+        http_client: &http_client,
+        config: &config,
+        reserve: U256::from(10u128.pow(19)),
+        history: &[],
+    };
+
+    // the node has no pool to quote the swap, so only the unwrap is sent
+    run_action(db.repo.clone(), id, &BeanSwap, &ctx).await.unwrap_err();
+    assert_eq!(node.sent(), 1);
+
+    let txs = transactions::search(
+        db.repo.clone(),
+        TransactionConditions { account_id: Some(id), ..Default::default() },
+    )
+    .await
+    .unwrap();
+    let rows = txs
+        .iter()
+        .map(|tx| {
+            (tx.action.as_str(), tx.protocol.as_str(), tx.token_in.as_deref(), tx.status.as_str())
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rows,
+        [("unwrap", "WMON", Some("wMON"), "success"), ("swap", "BEAN", Some("wMON"), "failed")]
+    );
+    assert_eq!(txs[0].token_out.as_deref(), Some("MON"));
+    assert_eq!(txs[0].amount_in, txs[1].amount_in);
+}
+
+#[tokio::test]
+async fn lets_the_accounts_in_flight_finish_on_a_shutdown() {
+    let db = TestDb::new("shutdown").await;
+    let mut config = config();
+    config.wmon_wrap_count = [2, 2];
+    config.action_delay = [1, 1];
+    config.max_concurrent_accounts = 1;
+    let ids = [
+        db.add_account(ANVIL_KEYS[0].0, ANVIL_KEYS[0].1, &config).await,
+        db.add_account(ANVIL_KEYS[1].0, ANVIL_KEYS[1].1, &config).await,
+    ];
+
+    // the shutdown is requested while the first wrap is being mined
+    let (sender, shutdown) = Shutdown::channel();
+    let node = MockNode::start_with(move || {
+        sender.send_replace(true);
+    })
+    .await;
+    warmup(
+        db.repo.clone(),
+        Arc::new(config),
+        node.connection(),
+        node.connection(),
+        false,
+        shutdown,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(node.sent(), 1);
+
+    let txs =
+        transactions::search(db.repo.clone(), TransactionConditions::default()).await.unwrap();
+    assert_eq!(txs.len(), 1);
+    assert_eq!((txs[0].action.as_str(), txs[0].status.as_str()), ("wrap", "success"));
+
+    // the account that got the only slot finishes its wrap, the other one never starts
+    let (started, waiting) = match txs[0].account_id == ids[0] {
+        true => (ids[0], ids[1]),
+        false => (ids[1], ids[0]),
+    };
+
+    let goals = account_goals::search_by_account_id(db.repo.clone(), started).await.unwrap();
+    let wrap = goals.iter().find(|goal| goal.action == "wrap").unwrap();
+    assert_eq!((wrap.current, wrap.target), (1, 2));
+    let account = accounts::search_account_by_id(db.repo.clone(), started).await.unwrap();
+    assert!(account.resume_delay.is_some());
+
+    let goals = account_goals::search_by_account_id(db.repo.clone(), waiting).await.unwrap();
+    assert!(goals.iter().all(|goal| goal.current == 0));
+    let account = accounts::search_account_by_id(db.repo.clone(), waiting).await.unwrap();
+    assert_eq!(account.resume_delay, Some(0));
+}
+
+#[tokio::test]
+async fn dry_run_sends_nothing_and_leaves_the_database_alone() {
+    let db = TestDb::new("dry-run").await;
+    let node = MockNode::start().await;
+    let config = config();
+    let id = db.add_account(ANVIL_KEYS[0].0, ANVIL_KEYS[0].1, &config).await;
+    let before = accounts::search_account_by_id(db.repo.clone(), id).await.unwrap();
+
+    // the dry run works on a copy of the database in `data`, where the wrap is simulated
+    let (_sender, shutdown) = Shutdown::channel();
+    let res = warmup(
+        db.repo.clone(),
+        Arc::new(config),
+        node.connection(),
+        node.connection(),
+        true,
+        shutdown,
+    )
+    .await;
+    let scratch = TestDb {
+        repo: create_repositories_at("data/database.dry_run.sqlite").await.unwrap(),
+        path: "data/database.dry_run.sqlite".into(),
+    };
+    res.unwrap();
+
+    let simulated =
+        transactions::search(scratch.repo.clone(), TransactionConditions::default()).await.unwrap();
+    let rows = simulated.iter().map(|tx| tx.action.as_str()).collect::<Vec<_>>();
+    assert_eq!(rows, ["wrap"]);
+    assert_eq!(node.sent(), 0);
+
+    let txs =
+        transactions::search(db.repo.clone(), TransactionConditions::default()).await.unwrap();
+    assert!(txs.is_empty());
+
+    let goals = account_goals::search_by_account_id(db.repo.clone(), id).await.unwrap();
+    assert!(goals.iter().all(|goal| goal.current == 0));
+
+    let after = accounts::search_account_by_id(db.repo.clone(), id).await.unwrap();
+    assert!(after.active);
+    assert_eq!(after.failure_count, before.failure_count);
+    assert_eq!(after.resume_delay, before.resume_delay);
+}